@@ -0,0 +1,185 @@
+pub use self::cluster::{
+    cluster_by_distance,
+    top_similar_pairs
+};
+
+/// Grouping of near-duplicate words
+mod cluster {
+    use std::collections::{HashMap, BinaryHeap};
+    use len_trait::len::Len;
+    use crate::graphemes_struct::Graphemes;
+    use crate::metrics::levenshtein_distance;
+
+    /// Groups words by single-linkage clustering: two words end up in the same group when a chain of words,
+    /// each within `max_distance` of the next, connects them. Pairs already in the same group are skipped
+    /// and a cheap bag distance lower bound rules out most distant pairs before computing their distance.
+    /// Groups are ordered by their first index and list indices into `items` in ascending order.
+    ///
+    /// # Arguments
+    /// * `items` - Words to cluster
+    /// * `sub_cost` - Cost of substituting a character with another
+    /// * `max_distance` - Largest levenshtein distance linking two words
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::cluster::cluster_by_distance;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let items = vec![Graphemes::from("color"), Graphemes::from("北京"), Graphemes::from("colour"), Graphemes::from("北京市")];
+    /// assert_eq!(cluster_by_distance(&items, 1, 1), vec![vec![0, 2], vec![1, 3]]);
+    /// ```
+    pub fn cluster_by_distance(items : &[Graphemes], sub_cost : usize, max_distance : usize) -> Vec<Vec<usize>> {
+        let mut parents : Vec<usize> = (0..items.len()).collect();
+        let grapheme_counts : Vec<HashMap<&str, usize>> = items.iter().map(|item| grapheme_counts(item)).collect();
+        for i in 0..items.len() {
+            for j in i+1..items.len() {
+                if find_root(&mut parents, i) == find_root(&mut parents, j) {
+                    continue;
+                }
+                if distance_lower_bound(&items[i], &items[j], &grapheme_counts[i], &grapheme_counts[j], sub_cost) > max_distance {
+                    continue;
+                }
+                if levenshtein_distance(&items[i], &items[j], sub_cost) <= max_distance {
+                    let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                    parents[root_i.max(root_j)] = root_i.min(root_j);
+                }
+            }
+        }
+
+        let mut clusters : Vec<Vec<usize>> = vec![];
+        let mut cluster_of_root : HashMap<usize, usize> = HashMap::new();
+        for i in 0..items.len() {
+            let root = find_root(&mut parents, i);
+            let cluster_index = *cluster_of_root.entry(root).or_insert_with(|| {
+                clusters.push(vec![]);
+                clusters.len() - 1
+            });
+            clusters[cluster_index].push(i);
+        }
+        clusters
+    }
+
+    /// Returns the `k` closest pairs of words as `(index1, index2, distance)` with `index1 < index2`,
+    /// sorted by distance and then by indices. Only the best `k` pairs found so far are kept,
+    /// and pairs whose bag distance lower bound cannot beat the worst of them are skipped.
+    ///
+    /// # Arguments
+    /// * `items` - Words to compare pairwise
+    /// * `sub_cost` - Cost of substituting a character with another
+    /// * `k` - Number of pairs to return
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::cluster::top_similar_pairs;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let items = vec![Graphemes::from("color"), Graphemes::from("北京"), Graphemes::from("colour"), Graphemes::from("北京市")];
+    /// assert_eq!(top_similar_pairs(&items, 1, 2), vec![(0, 2, 1), (1, 3, 1)]);
+    /// ```
+    pub fn top_similar_pairs(items : &[Graphemes], sub_cost : usize, k : usize) -> Vec<(usize, usize, usize)> {
+        if k == 0 {
+            return vec![];
+        }
+        let grapheme_counts : Vec<HashMap<&str, usize>> = items.iter().map(|item| grapheme_counts(item)).collect();
+        // max-heap of (distance, index1, index2) holding the best pairs so far
+        let mut best : BinaryHeap<(usize, usize, usize)> = BinaryHeap::with_capacity(k + 1);
+        for i in 0..items.len() {
+            for j in i+1..items.len() {
+                // pairs are visited in increasing index order, so a later pair only replaces an equally distant one if it is strictly closer
+                let worst = if best.len() == k { best.peek().map(|&(distance, _, _)| distance) } else { None };
+                let lower_bound = distance_lower_bound(&items[i], &items[j], &grapheme_counts[i], &grapheme_counts[j], sub_cost);
+                if worst.is_some_and(|worst| lower_bound >= worst) {
+                    continue;
+                }
+                let distance = levenshtein_distance(&items[i], &items[j], sub_cost);
+                if worst.is_some_and(|worst| distance >= worst) {
+                    continue;
+                }
+                best.push((distance, i, j));
+                if best.len() > k {
+                    best.pop();
+                }
+            }
+        }
+        best.into_sorted_vec().into_iter().map(|(distance, i, j)| (i, j, distance)).collect()
+    }
+
+    /// Union-find lookup with path halving
+    fn find_root(parents : &mut [usize], mut node : usize) -> usize {
+        while parents[node] != node {
+            parents[node] = parents[parents[node]];
+            node = parents[node];
+        }
+        node
+    }
+
+    pub(super) fn grapheme_counts<'a>(graphemes : &Graphemes<'a>) -> HashMap<&'a str, usize> {
+        let mut counts = HashMap::new();
+        for grapheme in graphemes.iter() {
+            *counts.entry(*grapheme).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Lower bound of the levenshtein distance from the multisets of graphemes (bag distance):
+    /// every edit fixes at most one surplus grapheme on each side.
+    /// Free substitutions only leave the difference in length.
+    pub(super) fn distance_lower_bound(graphemes1 : &Graphemes, graphemes2 : &Graphemes,
+                                       counts1 : &HashMap<&str, usize>, counts2 : &HashMap<&str, usize>, sub_cost : usize) -> usize {
+        if sub_cost == 0 {
+            return graphemes1.len().abs_diff(graphemes2.len());
+        }
+        let surplus = |counts : &HashMap<&str, usize>, other : &HashMap<&str, usize>| -> usize {
+            counts.iter().map(|(grapheme, &count)| count.saturating_sub(*other.get(grapheme).unwrap_or(&0))).sum()
+        };
+        surplus(counts1, counts2).max(surplus(counts2, counts1))
+    }
+}
+
+#[cfg(test)]
+mod test_cases {
+    use super::cluster::*;
+    use crate::graphemes_struct::Graphemes;
+    use crate::metrics::levenshtein_distance;
+
+    #[test]
+    fn cluster_by_distance_test() {
+        let items : Vec<Graphemes> = ["receive", "kitten", "recieve", "sitting", "receiver", "mitten", "北京烤鸭"]
+            .iter().map(|item| Graphemes::from(item)).collect();
+        assert_eq!(cluster_by_distance(&items, 1, 2), vec![vec![0, 2, 4], vec![1, 5], vec![3], vec![6]]);
+        // "kitten" links "mitten" and "sitting" together at a larger threshold
+        assert_eq!(cluster_by_distance(&items, 1, 3), vec![vec![0, 2, 4], vec![1, 3, 5], vec![6]]);
+        assert_eq!(cluster_by_distance(&items, 1, 0).len(), items.len());
+        assert!(cluster_by_distance(&[], 1, 2).is_empty());
+    }
+
+    #[test]
+    fn top_similar_pairs_test() {
+        let items : Vec<Graphemes> = ["receive", "kitten", "recieve", "sitting", "receiver", "mitten", "北京烤鸭"]
+            .iter().map(|item| Graphemes::from(item)).collect();
+        assert_eq!(top_similar_pairs(&items, 1, 3), vec![(0, 4, 1), (1, 5, 1), (0, 2, 2)]);
+        assert_eq!(top_similar_pairs(&items, 1, 1), vec![(0, 4, 1)]);
+        assert!(top_similar_pairs(&items, 1, 0).is_empty());
+        // asking for more pairs than exist returns every pair
+        let all_pairs = top_similar_pairs(&items, 1, 100);
+        assert_eq!(all_pairs.len(), items.len() * (items.len() - 1) / 2);
+        assert!(all_pairs.windows(2).all(|pairs| pairs[0].2 <= pairs[1].2));
+        for &(i, j, distance) in all_pairs.iter() {
+            assert_eq!(distance, levenshtein_distance(&items[i], &items[j], 1));
+        }
+        assert!(top_similar_pairs(&items[..1], 1, 3).is_empty());
+    }
+
+    #[test]
+    fn distance_lower_bound_test() {
+        let words = ["receive", "recieve", "kitten", "sitting", "", "北京烤鸭"];
+        for word1 in words.iter() {
+            for word2 in words.iter() {
+                let (graphemes1, graphemes2) = (Graphemes::from(word1), Graphemes::from(word2));
+                let (counts1, counts2) = (grapheme_counts(&graphemes1), grapheme_counts(&graphemes2));
+                for &sub_cost in [0, 1, 2].iter() {
+                    assert!(distance_lower_bound(&graphemes1, &graphemes2, &counts1, &counts2, sub_cost)
+                        <= levenshtein_distance(&graphemes1, &graphemes2, sub_cost));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+pub use self::diff::{
+    LineOp,
+    line_levenshtein,
+    line_edit_script,
+    diff_lines_streaming
+};
+
+/// Line-level comparison of documents
+mod diff {
+    use std::collections::VecDeque;
+    use std::io::{BufRead, Lines};
+    use crate::graphemes_struct::Graphemes;
+    use crate::metrics::levenshtein_distance;
+    use crate::{alignment_path_with, alignment_steps, AlignmentConfig, AlignmentStep};
+
+    /// Lines are aligned preferring gaps over substitutions on ties, so that a changed line is
+    /// substituted in place and added lines show up as insertions after it
+    const LINE_ALIGNMENT : AlignmentConfig = AlignmentConfig { sub_cost: 1, prefer_substitution: false };
+
+    /// Operation turning one line of the first text into the second text
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum LineOp {
+        /// Line present in both texts
+        Keep(String),
+        /// Line only present in the second text
+        Insert(String),
+        /// Line only present in the first text
+        Delete(String),
+        /// Line of the first text replaced by a line of the second text
+        Substitute(String, String),
+    }
+
+    fn split_lines(text : &str) -> Vec<Graphemes<'_>> {
+        text.lines().map(Graphemes::from).collect()
+    }
+
+    /// Calculates the levenshtein distance between two texts where every line is a single unit
+    ///
+    /// # Arguments
+    /// * `text1` - Text to compare with `text2`
+    /// * `text2` - Text to compare with `text1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::diff::line_levenshtein;
+    /// assert_eq!(line_levenshtein("a\nb\nc", "a\nB\nc\nd"), 2);
+    /// ```
+    pub fn line_levenshtein(text1 : &str, text2 : &str) -> usize {
+        levenshtein_distance(&split_lines(text1), &split_lines(text2), 1)
+    }
+
+    /// Returns the line operations of a minimal line-level alignment turning `text1` into `text2`
+    ///
+    /// # Arguments
+    /// * `text1` - Original text
+    /// * `text2` - Modified text
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::diff::{line_edit_script, LineOp};
+    /// assert_eq!(line_edit_script("a\nb", "a\nc"),
+    ///            vec![LineOp::Keep(String::from("a")), LineOp::Substitute(String::from("b"), String::from("c"))]);
+    /// ```
+    pub fn line_edit_script(text1 : &str, text2 : &str) -> Vec<LineOp> {
+        let (lines1, lines2) = (split_lines(text1), split_lines(text2));
+        let path = alignment_path_with(&lines1, &lines2, &LINE_ALIGNMENT);
+        alignment_steps(&lines1, &lines2, &path).into_iter().zip(path.windows(2)).map(|(step, coords)| {
+            let (row, col) = coords[1];
+            match step {
+                AlignmentStep::Match => LineOp::Keep(lines1[row-1].to_string()),
+                AlignmentStep::Substitute => LineOp::Substitute(lines1[row-1].to_string(), lines2[col-1].to_string()),
+                AlignmentStep::Insert => LineOp::Insert(lines2[col-1].to_string()),
+                AlignmentStep::Delete => LineOp::Delete(lines1[row-1].to_string()),
+            }
+        }).collect()
+    }
+
+    /// Number of lines of each reader aligned at once by `diff_lines_streaming`
+    const WINDOW : usize = 256;
+
+    /// Returns the line operations turning the lines of `reader1` into the lines of `reader2` like `line_edit_script`,
+    /// reading both lazily. Only a window of the next lines of each reader is aligned at a time, and the operations
+    /// are emitted up to the first line kept after a change, so memory stays bounded on huge inputs.
+    /// Changes spanning more than the window may give a longer script than `line_edit_script`.
+    ///
+    /// # Panics
+    /// Panics if reading a line fails, e.g. on invalid UTF-8.
+    ///
+    /// # Arguments
+    /// * `reader1` - Original text
+    /// * `reader2` - Modified text
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::diff::{diff_lines_streaming, LineOp};
+    /// let ops : Vec<LineOp> = diff_lines_streaming("a\nb\nc\n".as_bytes(), "a\nc\n".as_bytes()).collect();
+    /// assert_eq!(ops, vec![LineOp::Keep(String::from("a")), LineOp::Delete(String::from("b")), LineOp::Keep(String::from("c"))]);
+    /// ```
+    pub fn diff_lines_streaming<R1 : BufRead, R2 : BufRead>(reader1 : R1, reader2 : R2) -> impl Iterator<Item = LineOp> {
+        StreamingDiff::new(reader1, reader2, WINDOW)
+    }
+
+    pub(super) struct StreamingDiff<R1, R2> {
+        lines1 : Lines<R1>,
+        lines2 : Lines<R2>,
+        window1 : VecDeque<String>,
+        window2 : VecDeque<String>,
+        window : usize,
+        pending : VecDeque<LineOp>,
+    }
+
+    impl<R1 : BufRead, R2 : BufRead> StreamingDiff<R1, R2> {
+        pub(super) fn new(reader1 : R1, reader2 : R2, window : usize) -> StreamingDiff<R1, R2> {
+            StreamingDiff {
+                lines1: reader1.lines(),
+                lines2: reader2.lines(),
+                window1: VecDeque::with_capacity(window),
+                window2: VecDeque::with_capacity(window),
+                window: window.max(1),
+                pending: VecDeque::new(),
+            }
+        }
+
+        fn fill<R : BufRead>(lines : &mut Lines<R>, window : &mut VecDeque<String>, size : usize) {
+            while window.len() < size {
+                match lines.next() {
+                    Some(line) => window.push_back(line.expect("failed to read a line")),
+                    None => break,
+                }
+            }
+        }
+
+        /// Aligns the current windows and queues the operations up to the first line kept after a change
+        fn align_windows(&mut self) {
+            let steps = {
+                let lines1 : Vec<&str> = self.window1.iter().map(String::as_str).collect();
+                let lines2 : Vec<&str> = self.window2.iter().map(String::as_str).collect();
+                let path = alignment_path_with(&lines1, &lines2, &LINE_ALIGNMENT);
+                let steps = alignment_steps(&lines1, &lines2, &path);
+                // the windows hold every remaining line once both readers ran out before filling their window
+                let complete = lines1.len() < self.window && lines2.len() < self.window;
+                let mut end = steps.iter().position(|&step| step == AlignmentStep::Match).unwrap_or(steps.len());
+                if end == steps.len() && !complete {
+                    // not resynchronized within the windows: emit the changes up to half a window
+                    end = path.iter().position(|&(row, col)| row.max(col) >= self.window / 2).unwrap_or(steps.len()).max(1);
+                }
+                steps[..end].to_vec()
+            };
+            for step in steps {
+                self.pending.push_back(match step {
+                    AlignmentStep::Substitute => {
+                        let line1 = self.window1.pop_front().unwrap();
+                        LineOp::Substitute(line1, self.window2.pop_front().unwrap())
+                    }
+                    AlignmentStep::Insert => LineOp::Insert(self.window2.pop_front().unwrap()),
+                    AlignmentStep::Delete => LineOp::Delete(self.window1.pop_front().unwrap()),
+                    AlignmentStep::Match => unreachable!(),
+                });
+            }
+        }
+    }
+
+    impl<R1 : BufRead, R2 : BufRead> Iterator for StreamingDiff<R1, R2> {
+        type Item = LineOp;
+
+        fn next(&mut self) -> Option<LineOp> {
+            if let Some(op) = self.pending.pop_front() {
+                return Some(op);
+            }
+            Self::fill(&mut self.lines1, &mut self.window1, self.window);
+            Self::fill(&mut self.lines2, &mut self.window2, self.window);
+            if self.window1.front().is_some() && self.window1.front() == self.window2.front() {
+                self.window2.pop_front();
+                return self.window1.pop_front().map(LineOp::Keep);
+            }
+            if self.window1.is_empty() && self.window2.is_empty() {
+                return None;
+            }
+            self.align_windows();
+            self.pending.pop_front()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cases {
+    use super::diff::*;
+
+    const ORIGINAL : &str = "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+    const MODIFIED : &str = "fn main() {\n    let x = 2;\n    let y = 3;\n    println!(\"{}\", x);\n}\n";
+
+    #[test]
+    fn line_levenshtein_test() {
+        assert_eq!(line_levenshtein(ORIGINAL, MODIFIED), 2);
+        assert_eq!(line_levenshtein(ORIGINAL, ORIGINAL), 0);
+        // \r\n and a missing final newline do not count as changes
+        assert_eq!(line_levenshtein("北京\r\n烤鸭", "北京\n烤鸭\n"), 0);
+        assert_eq!(line_levenshtein("", "a\nb\n"), 2);
+    }
+
+    #[test]
+    fn line_edit_script_test() {
+        let line = |text : &str| String::from(text);
+        let script = line_edit_script(ORIGINAL, MODIFIED);
+        assert_eq!(script.len(), 5);
+        assert_eq!(script.iter().filter(|op| matches!(op, LineOp::Keep(_))).count(), 3);
+        assert!(script.contains(&LineOp::Keep(line("    println!(\"{}\", x);"))));
+        assert_eq!(line_edit_script("a\nb\nc", "a\nc"), vec![LineOp::Keep(line("a")), LineOp::Delete(line("b")), LineOp::Keep(line("c"))]);
+        assert_eq!(line_edit_script("a\nc", "a\nb\nc"), vec![LineOp::Keep(line("a")), LineOp::Insert(line("b")), LineOp::Keep(line("c"))]);
+        assert!(line_edit_script("", "").is_empty());
+    }
+
+    #[test]
+    fn diff_lines_streaming_test() {
+        let line = |text : &str| String::from(text);
+        let ops : Vec<LineOp> = diff_lines_streaming(ORIGINAL.as_bytes(), MODIFIED.as_bytes()).collect();
+        assert_eq!(ops, line_edit_script(ORIGINAL, MODIFIED));
+        assert_eq!(ops, vec![
+            LineOp::Keep(line("fn main() {")),
+            LineOp::Substitute(line("    let x = 1;"), line("    let x = 2;")),
+            LineOp::Insert(line("    let y = 3;")),
+            LineOp::Keep(line("    println!(\"{}\", x);")),
+            LineOp::Keep(line("}")),
+        ]);
+        let streamed : Vec<LineOp> = diff_lines_streaming("北京\r\n烤鸭".as_bytes(), "".as_bytes()).collect();
+        assert_eq!(streamed, vec![LineOp::Delete(line("北京")), LineOp::Delete(line("烤鸭"))]);
+        assert_eq!(diff_lines_streaming("".as_bytes(), "".as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn streaming_diff_small_window_test() {
+        let line = |text : &str| String::from(text);
+        // local changes far apart are found with a window much smaller than the texts
+        let text1 : String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        let text2 = text1.replace("line 10\n", "").replace("line 50\n", "line fifty\n").replace("line 90\n", "line 90\nextra\n");
+        let ops : Vec<LineOp> = StreamingDiff::new(text1.as_bytes(), text2.as_bytes(), 8).collect();
+        assert_eq!(ops, line_edit_script(&text1, &text2));
+        let changes : Vec<&LineOp> = ops.iter().filter(|op| !matches!(op, LineOp::Keep(_))).collect();
+        assert_eq!(changes, vec![&LineOp::Delete(line("line 10")), &LineOp::Substitute(line("line 50"), line("line fifty")), &LineOp::Insert(line("extra"))]);
+        // a change longer than the window still turns the first text into the second
+        let text3 : String = (0..30).map(|i| format!("other {}\n", i)).collect::<String>() + "line 99\n";
+        let ops : Vec<LineOp> = StreamingDiff::new(text1.as_bytes(), text3.as_bytes(), 8).collect();
+        let original : Vec<&String> = ops.iter().filter_map(|op| match op {
+            LineOp::Keep(line) | LineOp::Delete(line) | LineOp::Substitute(line, _) => Some(line),
+            LineOp::Insert(_) => None,
+        }).collect();
+        let modified : Vec<&String> = ops.iter().filter_map(|op| match op {
+            LineOp::Keep(line) | LineOp::Insert(line) | LineOp::Substitute(_, line) => Some(line),
+            LineOp::Delete(_) => None,
+        }).collect();
+        assert_eq!(original, text1.lines().collect::<Vec<&str>>());
+        assert_eq!(modified, text3.lines().collect::<Vec<&str>>());
+    }
+}
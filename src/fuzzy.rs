@@ -0,0 +1,538 @@
+pub use self::fuzzy::{
+    FuzzyMap,
+    PrefixDistanceIndex,
+    correct,
+    correct_sentence,
+    sort_fuzzy_candidates
+};
+
+/// Lookups tolerant to misspelled keys
+mod fuzzy {
+    use std::collections::HashMap;
+    use std::mem;
+    use len_trait::len::Len;
+    use push_trait::base::Push;
+    use crate::graphemes_struct::Graphemes;
+    use crate::metrics::levenshtein_distance;
+
+    /// A map from graphemes to values which can be queried with misspelled keys.
+    /// Keys are stored in a BK-tree over the levenshtein distance (substitution cost of 1),
+    /// so a lookup only visits the subtrees that can contain a key within the threshold.
+    pub struct FuzzyMap<'a, V> {
+        nodes : Vec<FuzzyNode<'a, V>>,
+    }
+
+    struct FuzzyNode<'a, V> {
+        key : Graphemes<'a>,
+        value : V,
+        /// distance to this node's key → index of the child node
+        children : HashMap<usize, usize>,
+    }
+
+    impl<'a, V> FuzzyMap<'a, V> {
+        pub fn new() -> FuzzyMap<'a, V> {
+            FuzzyMap {
+                nodes: vec![]
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.nodes.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.nodes.is_empty()
+        }
+
+        /// Inserts a key-value pair, returning the previous value if the key was already present
+        pub fn insert(&mut self, key : Graphemes<'a>, value : V) -> Option<V> {
+            if self.nodes.is_empty() {
+                self.nodes.push(FuzzyNode { key, value, children: HashMap::new() });
+                return None;
+            }
+            let new_index = self.nodes.len();
+            let mut node_index = 0;
+            loop {
+                let distance = levenshtein_distance(&self.nodes[node_index].key, &key, 1);
+                if distance == 0 {
+                    return Some(mem::replace(&mut self.nodes[node_index].value, value));
+                }
+                match self.nodes[node_index].children.get(&distance) {
+                    Some(&child_index) => node_index = child_index,
+                    None => {
+                        self.nodes[node_index].children.insert(distance, new_index);
+                        break;
+                    }
+                }
+            }
+            self.nodes.push(FuzzyNode { key, value, children: HashMap::new() });
+            None
+        }
+
+        /// Returns the value stored under exactly `key`
+        pub fn get(&self, key : &Graphemes) -> Option<&V> {
+            self.get_closest(key, 0).map(|(_, value)| value)
+        }
+
+        /// Returns the entry whose key is closest to `query`, if its distance is at most `max_distance`.
+        /// Ties are broken in favour of the key inserted first.
+        ///
+        /// # Arguments
+        /// * `query` - Possibly misspelled key
+        /// * `max_distance` - Largest levenshtein distance accepted between `query` and a key
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::fuzzy::FuzzyMap;
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let mut map = FuzzyMap::new();
+        /// map.insert(Graphemes::from("北京"), 1);
+        /// map.insert(Graphemes::from("南京"), 2);
+        /// map.insert(Graphemes::from("东京都"), 3);
+        /// let (key, value) = map.get_closest(&Graphemes::from("东京"), 1).unwrap();
+        /// assert_eq!((key.to_string(), *value), (String::from("北京"), 1));
+        /// assert!(map.get_closest(&Graphemes::from("上海"), 1).is_none());
+        /// ```
+        pub fn get_closest(&self, query : &Graphemes, max_distance : usize) -> Option<(&Graphemes<'a>, &V)> {
+            if self.nodes.is_empty() {
+                return None;
+            }
+            // (distance, node index)
+            let mut best : Option<(usize, usize)> = None;
+            let mut to_visit = vec![0];
+            while let Some(node_index) = to_visit.pop() {
+                let node = &self.nodes[node_index];
+                let distance = levenshtein_distance(&node.key, query, 1);
+                let closer = match best {
+                    Some(best) => (distance, node_index) < best,
+                    None => true,
+                };
+                if distance <= max_distance && closer {
+                    best = Some((distance, node_index));
+                }
+                // triangle inequality: only children whose edge is within `radius` of `distance` can be close enough
+                let radius = best.map_or(max_distance, |(best_distance, _)| best_distance);
+                to_visit.extend(node.children.iter()
+                    .filter(|&(&edge, _)| edge + radius >= distance && edge <= distance + radius)
+                    .map(|(_, &child_index)| child_index));
+            }
+            best.map(|(_, node_index)| (&self.nodes[node_index].key, &self.nodes[node_index].value))
+        }
+    }
+
+    impl<'a, V> Default for FuzzyMap<'a, V> {
+        fn default() -> Self {
+            FuzzyMap::new()
+        }
+    }
+
+    /// Candidates stored in a trie, so that the distances from a query to all of them are computed
+    /// in one traversal which shares the dynamic programming rows of common prefixes
+    pub struct PrefixDistanceIndex<'a> {
+        nodes : Vec<TrieNode<'a>>,
+        num_candidates : usize,
+    }
+
+    struct TrieNode<'a> {
+        /// grapheme → index of the child node
+        children : HashMap<&'a str, usize>,
+        /// indices of the candidates ending at this node
+        candidates : Vec<usize>,
+    }
+
+    impl<'a> PrefixDistanceIndex<'a> {
+        pub fn new() -> PrefixDistanceIndex<'a> {
+            PrefixDistanceIndex {
+                nodes: vec![TrieNode { children: HashMap::new(), candidates: vec![] }],
+                num_candidates: 0,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.num_candidates
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.num_candidates == 0
+        }
+
+        /// Adds a candidate, returning its index in the results of `distances`
+        pub fn insert(&mut self, candidate : &Graphemes<'a>) -> usize {
+            let mut node_index = 0;
+            for &grapheme in candidate.iter() {
+                let next_index = self.nodes.len();
+                node_index = match self.nodes[node_index].children.get(grapheme) {
+                    Some(&child_index) => child_index,
+                    None => {
+                        self.nodes[node_index].children.insert(grapheme, next_index);
+                        self.nodes.push(TrieNode { children: HashMap::new(), candidates: vec![] });
+                        next_index
+                    }
+                };
+            }
+            self.nodes[node_index].candidates.push(self.num_candidates);
+            self.num_candidates += 1;
+            self.num_candidates - 1
+        }
+
+        /// Returns the levenshtein distance from `query` to every candidate, in insertion order
+        ///
+        /// # Arguments
+        /// * `query` - Graphemes to compare with the candidates
+        /// * `sub_cost` - Cost of substituting a character with another
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::fuzzy::PrefixDistanceIndex;
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let mut index = PrefixDistanceIndex::new();
+        /// for candidate in ["北京", "北京烤鸭", "北海"].iter() {
+        ///     index.insert(&Graphemes::from(candidate));
+        /// }
+        /// assert_eq!(index.distances(&Graphemes::from("北京烤"), 1), vec![1, 1, 2]);
+        /// ```
+        pub fn distances(&self, query : &Graphemes, sub_cost : usize) -> Vec<usize> {
+            let mut distances = vec![0; self.num_candidates];
+            // (node index, row of the recurrence matrix for the node's prefix), candidates → rows, query → columns
+            let mut to_visit : Vec<(usize, Vec<usize>)> = vec![(0, (0..=query.len()).collect())];
+            while let Some((node_index, row)) = to_visit.pop() {
+                let node = &self.nodes[node_index];
+                for &candidate_index in node.candidates.iter() {
+                    distances[candidate_index] = row[query.len()];
+                }
+                for (&grapheme, &child_index) in node.children.iter() {
+                    let mut child_row = vec![row[0] + 1; query.len() + 1];
+                    for col in 1..=query.len() {
+                        child_row[col] = (row[col] + 1)
+                            .min(child_row[col-1] + 1)
+                            .min(row[col-1] + if query[col-1] == grapheme {0} else {sub_cost});
+                    }
+                    to_visit.push((child_index, child_row));
+                }
+            }
+            distances
+        }
+    }
+
+    impl<'a> Default for PrefixDistanceIndex<'a> {
+        fn default() -> Self {
+            PrefixDistanceIndex::new()
+        }
+    }
+
+    /// Corrects a possibly misspelled word to the most frequent dictionary word within `max_distance`
+    /// (levenshtein distance, substitution cost of 1). Ties in frequency go to the closer word, then
+    /// to the lexicographically smaller one. The word itself is a candidate like any other, so a known
+    /// but rare word may be corrected to a frequent neighbour.
+    ///
+    /// # Arguments
+    /// * `word` - Word to correct
+    /// * `dictionary` - Known words with their frequencies
+    /// * `max_distance` - Largest levenshtein distance between `word` and a candidate
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::fuzzy::correct;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// use std::collections::HashMap;
+    /// let mut dictionary = HashMap::new();
+    /// dictionary.insert(Graphemes::from("spelling"), 20);
+    /// dictionary.insert(Graphemes::from("spewing"), 2);
+    /// assert_eq!(correct(&Graphemes::from("speling"), &dictionary, 2), Some(Graphemes::from("spelling")));
+    /// // "spewing" is one edit away and "spelling" two, but the more frequent word wins
+    /// assert_eq!(correct(&Graphemes::from("speing"), &dictionary, 2), Some(Graphemes::from("spelling")));
+    /// assert_eq!(correct(&Graphemes::from("xyz"), &dictionary, 2), None);
+    /// ```
+    pub fn correct<'a>(word : &Graphemes, dictionary : &HashMap<Graphemes<'a>, usize>, max_distance : usize) -> Option<Graphemes<'a>> {
+        dictionary.iter()
+            .filter(|(candidate, _)| candidate.len().abs_diff(word.len()) <= max_distance)
+            .map(|(candidate, &frequency)| (candidate, frequency, levenshtein_distance(candidate, word, 1)))
+            .filter(|&(_, _, distance)| distance <= max_distance)
+            .min_by(|(candidate1, frequency1, distance1), (candidate2, frequency2, distance2)| {
+                frequency2.cmp(frequency1)
+                    .then(distance1.cmp(distance2))
+                    .then_with(|| candidate1[..].cmp(&candidate2[..]))
+            })
+            .map(|(candidate, _, _)| candidate.slice(0, candidate.len()))
+    }
+
+    /// Partial reading of a sentence: its log probability and the spans it covers,
+    /// each with the index of its word or `None` for a grapheme kept as it is
+    type Reading = (f64, Vec<(usize, usize, Option<usize>)>);
+
+    /// Probability of a single edit between an intended word and what was typed, used by `correct_sentence`
+    const EDIT_PROBABILITY : f64 = 0.001;
+
+    /// Segments a sentence and corrects every word at once, returning dictionary words separated by spaces.
+    /// A reading of the sentence is scored by the log probability of its words, taken from their frequencies,
+    /// with every edit between a word and the graphemes it covers multiplying the probability by 0.001.
+    /// Readings are built from left to right, keeping only the `beam` best at every position.
+    /// Whitespace in the sentence is ignored, and a grapheme no dictionary word can cover is kept as it is.
+    /// If every frequency is 0, all words are taken to be equally likely.
+    ///
+    /// # Arguments
+    /// * `sentence` - Sentence with possibly misspelled and unseparated words
+    /// * `dictionary` - Known words with their frequencies
+    /// * `max_edits` - Largest levenshtein distance between a word and the graphemes it covers
+    /// * `beam` - Number of partial readings kept at every position
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::fuzzy::correct_sentence;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// use std::collections::HashMap;
+    /// let dictionary : HashMap<Graphemes, usize> = [("北京", 10), ("烤鸭", 5), ("喜欢", 8), ("他", 20)].iter()
+    ///     .map(|&(word, frequency)| (Graphemes::from(word), frequency)).collect();
+    /// assert_eq!(correct_sentence(&Graphemes::from("他喜欢北京烤鸡"), &dictionary, 1, 5), Graphemes::from("他 喜欢 北京 烤鸭"));
+    /// ```
+    pub fn correct_sentence<'a>(sentence : &Graphemes<'a>, dictionary : &HashMap<Graphemes<'a>, usize>, max_edits : usize, beam : usize) -> Graphemes<'a> {
+        let mut text : Graphemes<'a> = Graphemes::new();
+        for &grapheme in sentence.iter().filter(|grapheme| !grapheme.chars().all(char::is_whitespace)) {
+            text.push(grapheme);
+        }
+        // sorted so that readings of equal score are ranked the same way every time
+        let mut words : Vec<(&Graphemes<'a>, usize)> = dictionary.iter().map(|(word, &frequency)| (word, frequency)).collect();
+        words.sort_by(|(word1, _), (word2, _)| word1[..].cmp(&word2[..]));
+        let total_frequency : usize = words.iter().map(|&(_, frequency)| frequency).sum();
+        // add-one smoothing when there are no counts at all, which would otherwise divide 0 by 0
+        let smoothing = if total_frequency == 0 { 1 } else { 0 };
+        let log_probabilities : Vec<f64> = words.iter()
+            .map(|&(_, frequency)| ((frequency + smoothing) as f64 / (total_frequency + smoothing * words.len()) as f64).ln())
+            .collect();
+        let max_span = words.iter().map(|(word, _)| word.len()).max().unwrap_or(0) + max_edits;
+        // a kept grapheme scores worse than any correction
+        let unknown_log_probability = EDIT_PROBABILITY.ln() * (max_edits + 1) as f64
+            + log_probabilities.iter().cloned().fold(0.0, f64::min);
+
+        let mut readings : Vec<Vec<Reading>> = vec![vec![]; text.len() + 1];
+        readings[0].push((0.0, vec![]));
+        for start in 0..text.len() {
+            let mut current = std::mem::take(&mut readings[start]);
+            current.sort_by(|(score1, _), (score2, _)| score2.total_cmp(score1));
+            current.truncate(beam.max(1));
+            let mut extensions : Vec<(usize, f64, Option<usize>)> = vec![(start + 1, unknown_log_probability, None)];
+            for end in start+1..=text.len().min(start + max_span) {
+                let span = text.slice(start, end);
+                for (index, &(word, _)) in words.iter().enumerate() {
+                    if word.len().abs_diff(span.len()) > max_edits {
+                        continue;
+                    }
+                    let distance = levenshtein_distance(word, &span, 1);
+                    if distance <= max_edits {
+                        extensions.push((end, log_probabilities[index] + EDIT_PROBABILITY.ln() * distance as f64, Some(index)));
+                    }
+                }
+            }
+            for (score, spans) in current.iter() {
+                for &(end, log_probability, word) in extensions.iter() {
+                    let mut extended = spans.clone();
+                    extended.push((start, end, word));
+                    readings[end].push((score + log_probability, extended));
+                }
+            }
+        }
+
+        let mut complete = std::mem::take(&mut readings[text.len()]);
+        complete.sort_by(|(score1, _), (score2, _)| score2.total_cmp(score1));
+        let best = complete.into_iter().next().map(|(_, spans)| spans).unwrap_or_default();
+        let mut corrected : Graphemes<'a> = Graphemes::new();
+        for (i, &(start, end, word)) in best.iter().enumerate() {
+            if i > 0 {
+                corrected.push(" ");
+            }
+            match word {
+                Some(index) => corrected.append(words[index].0.slice(0, words[index].0.len())),
+                None => corrected.append(text.slice(start, end)),
+            }
+        }
+        corrected
+    }
+
+    /// Sorts candidate corrections of `query` from best to worst by a levenshtein distance in which edits near the
+    /// start of the query cost slightly more: an edit before query grapheme `i` is weighted `1 + 0.5 / (i + 1)`,
+    /// so an error in the first grapheme costs 1.5 and errors further back approach 1. Ties keep the order of `candidates`.
+    ///
+    /// # Arguments
+    /// * `query` - Possibly misspelled word
+    /// * `candidates` - Suggestions to order
+    /// * `sub_cost` - Cost of substituting a character with another, before weighting
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::fuzzy::sort_fuzzy_candidates;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let candidates = vec![Graphemes::from("bat"), Graphemes::from("cab")];
+    /// assert_eq!(sort_fuzzy_candidates(&Graphemes::from("cat"), &candidates, 1), vec![Graphemes::from("cab"), Graphemes::from("bat")]);
+    /// ```
+    pub fn sort_fuzzy_candidates<'a>(query : &Graphemes, candidates : &[Graphemes<'a>], sub_cost : usize) -> Vec<Graphemes<'a>> {
+        let mut scored : Vec<(f64, &Graphemes<'a>)> = candidates.iter()
+            .map(|candidate| (front_weighted_distance(query, candidate, sub_cost), candidate))
+            .collect();
+        scored.sort_by(|(distance1, _), (distance2, _)| distance1.partial_cmp(distance2).unwrap());
+        scored.into_iter().map(|(_, candidate)| candidate.slice(0, candidate.len())).collect()
+    }
+
+    /// Weight of an edit made before grapheme `position` of the query
+    fn front_weight(position : usize) -> f64 {
+        1.0 + 0.5 / (position + 1) as f64
+    }
+
+    /// Levenshtein distance from `query` to `candidate` with every edit weighted by `front_weight`
+    /// of its position in the query
+    fn front_weighted_distance(query : &Graphemes, candidate : &Graphemes, sub_cost : usize) -> f64 {
+        let mut previous_row : Vec<f64> = (0..=candidate.len()).map(|col| col as f64 * front_weight(0)).collect();
+        for row in 1..=query.len() {
+            let mut current_row = vec![previous_row[0] + front_weight(row - 1); candidate.len() + 1];
+            for col in 1..=candidate.len() {
+                let substitution = if query[row-1] == candidate[col-1] { 0.0 } else { sub_cost as f64 * front_weight(row - 1) };
+                current_row[col] = (previous_row[col] + front_weight(row - 1))
+                    .min(current_row[col-1] + front_weight(row))
+                    .min(previous_row[col-1] + substitution);
+            }
+            previous_row = current_row;
+        }
+        previous_row[candidate.len()]
+    }
+}
+
+#[cfg(test)]
+mod test_cases {
+    use super::fuzzy::{FuzzyMap, PrefixDistanceIndex, correct, correct_sentence, sort_fuzzy_candidates};
+    use crate::graphemes_struct::Graphemes;
+    use crate::metrics::levenshtein_distance;
+    use std::collections::HashMap;
+
+    fn commit() -> &'static str { "committed" }
+    fn checkout() -> &'static str { "checked out" }
+    fn status() -> &'static str { "clean" }
+    fn push() -> &'static str { "pushed" }
+    fn pull() -> &'static str { "pulled" }
+
+    fn commands() -> FuzzyMap<'static, fn() -> &'static str> {
+        let mut map : FuzzyMap<fn() -> &'static str> = FuzzyMap::new();
+        map.insert(Graphemes::from("commit"), commit);
+        map.insert(Graphemes::from("checkout"), checkout);
+        map.insert(Graphemes::from("status"), status);
+        map.insert(Graphemes::from("push"), push);
+        map.insert(Graphemes::from("pull"), pull);
+        map
+    }
+
+    #[test]
+    fn fuzzy_map_command_test() {
+        let map = commands();
+        assert_eq!(map.len(), 5);
+        let (key, handler) = map.get_closest(&Graphemes::from("comit"), 2).unwrap();
+        assert_eq!(key, &Graphemes::from("commit"));
+        assert_eq!(handler(), "committed");
+        assert_eq!(map.get_closest(&Graphemes::from("stauts"), 2).unwrap().1(), "clean");
+        assert_eq!(map.get_closest(&Graphemes::from("pusj"), 1).unwrap().1(), "pushed");
+        assert!(map.get_closest(&Graphemes::from("rebase"), 2).is_none());
+        assert_eq!(map.get(&Graphemes::from("pull")).unwrap()(), "pulled");
+        assert!(map.get(&Graphemes::from("pul")).is_none());
+    }
+
+    #[test]
+    fn fuzzy_map_insert_test() {
+        let mut map = FuzzyMap::new();
+        assert!(map.is_empty());
+        assert!(map.get_closest(&Graphemes::from("a"), 5).is_none());
+        assert_eq!(map.insert(Graphemes::from("a"), 1), None);
+        assert_eq!(map.insert(Graphemes::from("b"), 2), None);
+        assert_eq!(map.insert(Graphemes::from("a"), 3), Some(1));
+        assert_eq!(map.len(), 2);
+        // "c" is distance 1 from both keys, the first inserted key wins
+        assert_eq!(map.get_closest(&Graphemes::from("c"), 1), Some((&Graphemes::from("a"), &3)));
+    }
+
+    #[test]
+    fn correct_test() {
+        let mut dictionary = HashMap::new();
+        dictionary.insert(Graphemes::from("the"), 1000);
+        dictionary.insert(Graphemes::from("they"), 100);
+        dictionary.insert(Graphemes::from("thy"), 1);
+        dictionary.insert(Graphemes::from("tho"), 1);
+        dictionary.insert(Graphemes::from("北京"), 50);
+        // "thy" and "the" are both one edit away, the frequent "the" wins
+        assert_eq!(correct(&Graphemes::from("thw"), &dictionary, 1), Some(Graphemes::from("the")));
+        // a frequent word outranks a closer but rare one: "thy" is one edit away, "the" and "they" two
+        assert_eq!(levenshtein_distance(&Graphemes::from("thyz"), &Graphemes::from("thy"), 1), 1);
+        assert_eq!(levenshtein_distance(&Graphemes::from("thyz"), &Graphemes::from("the"), 1), 2);
+        assert_eq!(correct(&Graphemes::from("thyz"), &dictionary, 2), Some(Graphemes::from("the")));
+        assert_eq!(correct(&Graphemes::from("thyz"), &dictionary, 1), Some(Graphemes::from("thy")));
+        // "the" is out of reach at distance 1, "they" is the most frequent remaining candidate
+        assert_eq!(correct(&Graphemes::from("theyy"), &dictionary, 1), Some(Graphemes::from("they")));
+        // equal frequencies fall back to the closer word, then the smaller one
+        assert_eq!(correct(&Graphemes::from("thy"), &dictionary, 0), Some(Graphemes::from("thy")));
+        assert_eq!(correct(&Graphemes::from("thx"), &dictionary, 1), Some(Graphemes::from("the")));
+        dictionary.remove(&Graphemes::from("the"));
+        dictionary.remove(&Graphemes::from("they"));
+        assert_eq!(correct(&Graphemes::from("thx"), &dictionary, 1), Some(Graphemes::from("tho")));
+        assert_eq!(correct(&Graphemes::from("南京"), &dictionary, 1), Some(Graphemes::from("北京")));
+        assert_eq!(correct(&Graphemes::from("上海"), &dictionary, 1), None);
+    }
+
+    #[test]
+    fn correct_sentence_test() {
+        let dictionary : HashMap<Graphemes, usize> = [
+            ("we", 100), ("can", 80), ("only", 50), ("see", 60), ("a", 200), ("short", 30), ("distance", 20), ("ahead", 10),
+            ("canon", 2), ("ash", 3), ("sort", 5), ("head", 15),
+        ].iter().map(|&(word, frequency)| (Graphemes::from(word), frequency)).collect();
+        let correct = |sentence| correct_sentence(&Graphemes::from(sentence), &dictionary, 2, 10).to_string();
+        // "olny" and "distanse" are misspelled
+        assert_eq!(correct("wecanolnyseeashortdistanseahead"), "we can only see a short distance ahead");
+        assert_eq!(correct("we can see a short distance ahead"), "we can see a short distance ahead");
+        // without edits, a grapheme no word covers is kept
+        assert_eq!(correct_sentence(&Graphemes::from("we北see"), &dictionary, 0, 10), Graphemes::from("we 北 see"));
+        // otherwise it is cheaper to drop it from a neighbouring word
+        assert_eq!(correct("we北see"), "we see");
+        assert_eq!(correct(""), "");
+        assert_eq!(correct_sentence(&Graphemes::from("we"), &HashMap::new(), 1, 3), Graphemes::from("w e"));
+    }
+
+    #[test]
+    fn correct_sentence_zero_frequency_test() {
+        let unseen : HashMap<Graphemes, usize> = [(Graphemes::from("we"), 0)].iter().cloned().collect();
+        assert_eq!(correct_sentence(&Graphemes::from("we"), &unseen, 1, 3), Graphemes::from("we"));
+        assert_eq!(correct_sentence(&Graphemes::from("wee"), &unseen, 1, 3), Graphemes::from("we"));
+        // words without counts are equally likely, so the reading with fewer edits wins
+        let unseen : HashMap<Graphemes, usize> = ["we", "see", "sea"].iter().map(|&word| (Graphemes::from(word), 0)).collect();
+        assert_eq!(correct_sentence(&Graphemes::from("wesee"), &unseen, 1, 10), Graphemes::from("we see"));
+        assert_eq!(correct_sentence(&Graphemes::from("wesae"), &unseen, 1, 10), Graphemes::from("we see"));
+        assert_eq!(correct_sentence(&Graphemes::from("wesea"), &unseen, 1, 10), Graphemes::from("we sea"));
+    }
+
+    #[test]
+    fn sort_fuzzy_candidates_test() {
+        let query = Graphemes::from("hello");
+        let candidates : Vec<Graphemes> = ["jello", "helo", "hellp", "hxllo", "help", "hello", "yellow"]
+            .iter().map(|candidate| Graphemes::from(candidate)).collect();
+        let sorted : Vec<String> = sort_fuzzy_candidates(&query, &candidates, 1).iter().map(|candidate| candidate.to_string()).collect();
+        // single edits rank by how far back they are, and the front penalty never outweighs an extra edit here
+        assert_eq!(sorted, vec!["hello", "hellp", "helo", "hxllo", "jello", "help", "yellow"]);
+        // equally weighted candidates keep their order
+        let ties = vec![Graphemes::from("北京"), Graphemes::from("南京"), Graphemes::from("东京")];
+        assert_eq!(sort_fuzzy_candidates(&Graphemes::from("西京"), &ties, 1), ties);
+        assert!(sort_fuzzy_candidates(&query, &[], 1).is_empty());
+    }
+
+    #[test]
+    fn prefix_distance_index_test() {
+        let candidates : Vec<Graphemes> = ["commit", "command", "comment", "com", "", "checkout", "北京", "北京烤鸭", "command"]
+            .iter().map(|candidate| Graphemes::from(candidate)).collect();
+        let mut index = PrefixDistanceIndex::new();
+        assert!(index.is_empty());
+        for (i, candidate) in candidates.iter().enumerate() {
+            assert_eq!(index.insert(candidate), i);
+        }
+        assert_eq!(index.len(), candidates.len());
+        for query in ["comit", "", "commander", "北京烤", "x"].iter() {
+            let query = Graphemes::from(query);
+            for &sub_cost in [0, 1, 2].iter() {
+                let expected : Vec<usize> = candidates.iter().map(|candidate| levenshtein_distance(candidate, &query, sub_cost)).collect();
+                assert_eq!(index.distances(&query, sub_cost), expected);
+            }
+        }
+        assert!(PrefixDistanceIndex::new().distances(&Graphemes::from("a"), 1).is_empty());
+    }
+}
@@ -1,19 +1,44 @@
-pub use self::graphemes_struct::Graphemes;
+pub use self::graphemes_struct::{Graphemes, OwnedGraphemes, NormalizationConfig};
+pub use unicode_script::Script;
+pub(crate) use self::graphemes_struct::{grapheme_width, WrapCell, wrap_ranges};
 
 /// Vector of graphemes
 mod graphemes_struct {
     extern crate unicode_segmentation;
+    extern crate unicode_width;
+    extern crate unicode_script;
+    extern crate unicode_normalization;
     use unicode_segmentation::UnicodeSegmentation;
+    use unicode_script::{Script, UnicodeScript};
+    use unicode_normalization::UnicodeNormalization;
+    use unicode_width::UnicodeWidthStr;
     use std::ops::{Deref, Index, IndexMut};
     use std::fmt::{Display, Formatter};
     use std::fmt;
     use len_trait::len::{Len, Empty, Clear};
     use push_trait::base::{Push, CanPush};
     use std::slice::SliceIndex;
+    use std::collections::HashSet;
+    use std::iter::FromIterator;
+    use crate::metrics::levenshtein_distance;
+    #[cfg(feature = "serde")]
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+    /// Normalizations applied by `Graphemes::from_normalized` on top of NFC
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct NormalizationConfig {
+        /// Uppercase then lowercase every character, the case mapping of `Graphemes::eq_ignore_case`.
+        /// This is close to, but not the same as, Unicode case folding
+        pub ignore_case : bool,
+        /// Convert full-width ASCII to half-width, like `Graphemes::normalize_width`
+        pub normalize_width : bool,
+    }
 
     /// A vector of graphemes.
     /// Graphemes can vary in size which is why Vec<&str> is used.
-    #[derive(Debug, Hash, Eq, PartialEq)]
+    /// Graphemes are ordered like `Vec<&str>`: lexicographically by grapheme, each grapheme by its bytes,
+    /// which is code point order rather than the collation of any locale.
+    #[derive(Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord)]
     pub struct Graphemes<'a> {
         graphemes : Vec<&'a str>,
     }
@@ -32,6 +57,90 @@ mod graphemes_struct {
             }
         }
 
+        /// Normalizes a string to NFC, optionally ignoring case and normalizing width, and segments the result
+        /// into graphemes owning their strings. Case mapping and NFC are done together in one pass over the
+        /// characters into a temporary string, which is then segmented. Widths are normalized while the
+        /// graphemes are copied out of it.
+        ///
+        /// # Arguments
+        /// * `string` - String to normalize and segment
+        /// * `config` - Normalizations to apply on top of NFC
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::{Graphemes, NormalizationConfig};
+        /// let config = NormalizationConfig { ignore_case: true, normalize_width: true };
+        /// assert_eq!(Graphemes::from_normalized("Ｃafe\u{301}", config), Graphemes::from("café"));
+        /// ```
+        pub fn from_normalized(string : &str, config : NormalizationConfig) -> OwnedGraphemes {
+            let normalized : String = if config.ignore_case {
+                string.chars().flat_map(char::to_uppercase).flat_map(char::to_lowercase).nfc().collect()
+            } else {
+                string.nfc().collect()
+            };
+            let graphemes = Graphemes::from(&normalized);
+            if config.normalize_width {
+                graphemes.normalize_width().to_owned()
+            } else {
+                graphemes.to_owned()
+            }
+        }
+
+        /// Segments a string into extended grapheme clusters like `from`.
+        /// When `emoji_aware` is false, emoji ZWJ sequences are split into their components with each
+        /// zero width joiner as its own grapheme, and flags are split into their two regional indicators,
+        /// so that the source string is still reproduced by `to_string`.
+        ///
+        /// # Arguments
+        /// * `string` - String to segment
+        /// * `emoji_aware` - Whether ZWJ sequences and flags are kept as single graphemes
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let family = "👨\u{200D}👩\u{200D}👧";
+        /// assert_eq!(Graphemes::from_emoji_aware(family, true).len(), 1);
+        /// assert_eq!(Graphemes::from_emoji_aware(family, false).len(), 5);
+        /// ```
+        pub fn from_emoji_aware(string : &'a str, emoji_aware : bool) -> Graphemes<'a> {
+            if emoji_aware {
+                return Graphemes::from(string);
+            }
+            let graphemes = UnicodeSegmentation::graphemes(string, true)
+                .flat_map(split_emoji_sequence)
+                .collect::<Vec<&str>>();
+            Graphemes {
+                graphemes,
+            }
+        }
+
+        /// Builds graphemes by repeating every grapheme of `runs` as often as its run length, reversing `rle`
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from_rle(&[("a", 3), ("北", 1)]), Graphemes::from("aaa北"));
+        /// ```
+        pub fn from_rle(runs : &[(&'a str, usize)]) -> Graphemes<'a> {
+            let mut graphemes = Vec::with_capacity(runs.iter().map(|&(_, run_length)| run_length).sum());
+            for &(grapheme, run_length) in runs.iter() {
+                graphemes.resize(graphemes.len() + run_length, grapheme);
+            }
+            Graphemes { graphemes }
+        }
+
+        /// Copies every grapheme into an `OwnedGraphemes`, which does not borrow from the source string
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::{Graphemes, OwnedGraphemes};
+        /// let owned : OwnedGraphemes = Graphemes::from("北京").to_owned();
+        /// assert_eq!(owned, Graphemes::from("北京"));
+        /// ```
+        pub fn to_owned(&self) -> OwnedGraphemes {
+            OwnedGraphemes { graphemes: self.graphemes.iter().map(|grapheme| grapheme.to_string()).collect() }
+        }
+
         pub fn get(&self, index : usize) -> Option<&&str> {
             self.graphemes.get(index)
         }
@@ -40,6 +149,18 @@ mod graphemes_struct {
             self.graphemes.reverse();
         }
 
+        /// Returns a copy with the graphemes in reverse order
+        pub fn reversed(&self) -> Graphemes<'a> {
+            Graphemes { graphemes: self.graphemes.iter().rev().cloned().collect() }
+        }
+
+        /// Returns the minimum number of graphemes to delete to make this a palindrome.
+        /// This is half the insertion/deletion distance between the graphemes and their reverse.
+        pub fn palindrome_distance(&self) -> usize {
+            // a substitution costing 2 is never cheaper than a deletion plus an insertion
+            levenshtein_distance(self, &self.reversed(), 2) / 2
+        }
+
         pub fn slice(&self, start : usize, end : usize) -> Self {
             let graphemes = self.graphemes[start..end].to_vec();
             Graphemes { graphemes }
@@ -49,10 +170,363 @@ mod graphemes_struct {
             self.graphemes.append(&mut other.graphemes);
         }
 
-        pub fn split(&self, splitter : &'a str) -> Vec<Graphemes> {
+        pub fn split(&self, splitter : &'a str) -> Vec<Graphemes<'a>> {
             self.graphemes.split(|character| *character == splitter).map(
                 |str_arr| Graphemes { graphemes: str_arr.to_vec()} ).collect()
         }
+
+        /// Splits on every grapheme in `separators`. Like `split`, adjacent separators give empty pieces.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// use std::collections::HashSet;
+        /// let separators : HashSet<&str> = [" ", "，"].iter().cloned().collect();
+        /// assert_eq!(Graphemes::from("北京，烤鸭 好").split_any(&separators),
+        ///            vec![Graphemes::from("北京"), Graphemes::from("烤鸭"), Graphemes::from("好")]);
+        /// ```
+        pub fn split_any(&self, separators : &HashSet<&str>) -> Vec<Graphemes<'a>> {
+            self.graphemes.split(|grapheme| separators.contains(grapheme)).map(
+                |str_arr| Graphemes { graphemes: str_arr.to_vec() }).collect()
+        }
+
+        /// Splits into consecutive non-overlapping pieces of `size` graphemes, the last one possibly shorter
+        pub fn chunks(&self, size : usize) -> Vec<Graphemes<'a>> {
+            self.graphemes.chunks(size).map(|chunk| Graphemes { graphemes: chunk.to_vec() }).collect()
+        }
+
+        /// Returns every run of `size` consecutive graphemes, overlapping
+        pub fn windows(&self, size : usize) -> Vec<Graphemes<'a>> {
+            self.graphemes.windows(size).map(|window| Graphemes { graphemes: window.to_vec() }).collect()
+        }
+
+        /// Returns a copy without leading and trailing whitespace graphemes, including Unicode
+        /// whitespace such as the full-width space U+3000
+        pub fn trim(&self) -> Graphemes<'a> {
+            let start = self.graphemes.iter().take_while(|grapheme| is_whitespace(grapheme)).count();
+            let end = self.graphemes.len() - self.graphemes[start..].iter().rev().take_while(|grapheme| is_whitespace(grapheme)).count();
+            self.slice(start, end)
+        }
+
+        /// Returns a copy without leading whitespace graphemes
+        pub fn trim_start(&self) -> Graphemes<'a> {
+            let start = self.graphemes.iter().take_while(|grapheme| is_whitespace(grapheme)).count();
+            self.slice(start, self.graphemes.len())
+        }
+
+        /// Returns a copy without trailing whitespace graphemes
+        pub fn trim_end(&self) -> Graphemes<'a> {
+            let end = self.graphemes.len() - self.graphemes.iter().rev().take_while(|grapheme| is_whitespace(grapheme)).count();
+            self.slice(0, end)
+        }
+
+        /// Returns a copy with full-width ASCII graphemes (U+FF01 to U+FF5E) and the ideographic space
+        /// converted to their half-width forms, e.g. "Ａ１" becomes "A1"
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from("Ａ１　北京").normalize_width(), Graphemes::from("A1 北京"));
+        /// ```
+        pub fn normalize_width(&self) -> Graphemes<'a> {
+            let graphemes = self.graphemes.iter().map(|&grapheme| {
+                let mut chars = grapheme.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('\u{3000}'), None) => " ",
+                    (Some(c @ '\u{FF01}'..='\u{FF5E}'), None) => {
+                        let ascii_index = c as usize - 0xFF01 + 1;
+                        &HALF_WIDTH_ASCII[ascii_index..ascii_index + 1]
+                    }
+                    _ => grapheme,
+                }
+            }).collect();
+            Graphemes { graphemes }
+        }
+
+        /// Returns a copy with half-width katakana (U+FF61 to U+FF9F) converted to full-width,
+        /// including voiced sound marks combined with their base, e.g. "ｶﾞ" becomes "ガ"
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from("ﾃﾞｰﾀ").widen_kana(), Graphemes::from("データ"));
+        /// ```
+        pub fn widen_kana(&self) -> Graphemes<'a> {
+            let graphemes = self.graphemes.iter().map(|&grapheme| {
+                let mut chars = grapheme.chars();
+                match (chars.next().and_then(full_width_kana), chars.next(), chars.next()) {
+                    (Some(kana), None, _) => kana,
+                    (Some(kana), Some(mark @ '\u{FF9E}'..='\u{FF9F}'), None) => voiced_kana(kana, mark == '\u{FF9F}').unwrap_or(grapheme),
+                    _ => grapheme,
+                }
+            }).collect();
+            Graphemes { graphemes }
+        }
+
+        /// Breaks the graphemes into lines of at most `width` terminal columns. Lines are broken at
+        /// the last whitespace that fits, or between graphemes when a word is longer than a line, and
+        /// at every newline. A grapheme wider than `width` gets a line of its own.
+        /// Whitespace at soft line breaks is dropped.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let lines = Graphemes::from("we like 北京烤鸭").wrap(7);
+        /// assert_eq!(lines, vec![Graphemes::from("we like"), Graphemes::from("北京烤"), Graphemes::from("鸭")]);
+        /// ```
+        pub fn wrap(&self, width : usize) -> Vec<Graphemes<'a>> {
+            assert!(width > 0, "lines must be at least one column wide");
+            let cells : Vec<WrapCell> = self.graphemes.iter().map(|grapheme| WrapCell::of(grapheme)).collect();
+            wrap_ranges(&cells, width).into_iter().map(|(start, end)| self.slice(start, end)).collect()
+        }
+
+        /// Returns a copy with `f` applied to every grapheme, e.g. to apply a transliteration table.
+        /// `f` may return its input or any string living as long as the graphemes, such as a `&'static str`.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let simplified = Graphemes::from("東京").map_graphemes(|grapheme| if grapheme == "東" {"东"} else {grapheme});
+        /// assert_eq!(simplified, Graphemes::from("东京"));
+        /// ```
+        pub fn map_graphemes<F>(&self, f : F) -> Graphemes<'a>
+            where F : Fn(&'a str) -> &'a str {
+            Graphemes { graphemes: self.graphemes.iter().map(|&grapheme| f(grapheme)).collect() }
+        }
+
+        /// Compares with `other` ignoring case, without allocating normalized copies.
+        /// Characters are folded by uppercasing and then lowercasing them, which follows the full
+        /// Unicode case folding without language specific rules: "ß" equals "SS", and the Turkish
+        /// dotted capital "İ" folds to "i̇" ("i" with a combining dot), so it does not equal "i",
+        /// while the dotless "ı" uppercases to "I" and so equals "i".
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert!(Graphemes::from("Straße").eq_ignore_case(&Graphemes::from("STRASSE")));
+        /// assert!(!Graphemes::from("İ").eq_ignore_case(&Graphemes::from("i")));
+        /// ```
+        pub fn eq_ignore_case(&self, other : &Graphemes) -> bool {
+            fold_case(&self.graphemes).eq(fold_case(&other.graphemes))
+        }
+
+        /// Returns the Unicode script of every grapheme, taken from its first character which is
+        /// not a combining mark (script `Inherited`). Punctuation, digits and spaces are `Common`.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::{Graphemes, Script};
+        /// assert_eq!(Graphemes::from("a北!").scripts(), vec![Script::Latin, Script::Han, Script::Common]);
+        /// ```
+        pub fn scripts(&self) -> Vec<Script> {
+            self.graphemes.iter().map(|grapheme| {
+                grapheme.chars().map(|c| c.script()).find(|&script| script != Script::Inherited).unwrap_or(Script::Inherited)
+            }).collect()
+        }
+
+        /// Returns the byte offset at which every grapheme starts in the string the graphemes were made from,
+        /// followed by the byte length of that string. The offsets index into `to_string()` of the graphemes,
+        /// which is the original string unless graphemes were replaced or removed.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from("a北é").byte_offsets(), vec![0, 1, 4, 6]);
+        /// ```
+        pub fn byte_offsets(&self) -> Vec<usize> {
+            let mut offsets = Vec::with_capacity(self.graphemes.len() + 1);
+            offsets.push(0);
+            for grapheme in self.graphemes.iter() {
+                offsets.push(offsets[offsets.len() - 1] + grapheme.len());
+            }
+            offsets
+        }
+
+        /// Run-length encodes the graphemes as (grapheme, run length) pairs of consecutive equal graphemes
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from("sooo good").rle(), vec![("s", 1), ("o", 3), (" ", 1), ("g", 1), ("o", 2), ("d", 1)]);
+        /// ```
+        pub fn rle(&self) -> Vec<(&'a str, usize)> {
+            let mut runs : Vec<(&'a str, usize)> = vec![];
+            for &grapheme in self.graphemes.iter() {
+                match runs.last_mut() {
+                    Some((last, run_length)) if *last == grapheme => *run_length += 1,
+                    _ => runs.push((grapheme, 1)),
+                }
+            }
+            runs
+        }
+
+        /// Keeps only the graphemes satisfying `predicate`, in place and in order, like `Vec::retain`
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let mut graphemes = Graphemes::from("北京, 烤鸭!");
+        /// graphemes.retain(|grapheme| grapheme.chars().all(char::is_alphanumeric));
+        /// assert_eq!(graphemes, Graphemes::from("北京烤鸭"));
+        /// ```
+        pub fn retain<F>(&mut self, mut predicate : F)
+            where F : FnMut(&str) -> bool {
+            self.graphemes.retain(|grapheme| predicate(grapheme));
+        }
+
+        /// Counts the graphemes satisfying `predicate`
+        pub fn count_matches<F>(&self, predicate : F) -> usize
+            where F : Fn(&str) -> bool {
+            self.graphemes.iter().filter(|grapheme| predicate(grapheme)).count()
+        }
+
+        /// Returns whether `needle` appears as a contiguous run of graphemes. Matches only start and end on
+        /// grapheme boundaries, so a part of a grapheme cluster is never found. An empty needle is always contained.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert!(Graphemes::from("北京烤鸭").contains(&Graphemes::from("京烤")));
+        /// // "e" is only part of the cluster "e\u{301}"
+        /// assert!("cafe\u{301}".contains("e"));
+        /// assert!(!Graphemes::from("cafe\u{301}").contains(&Graphemes::from("e")));
+        /// ```
+        pub fn contains(&self, needle : &Graphemes) -> bool {
+            self.find(needle).is_some()
+        }
+
+        /// Returns the grapheme index at which the first occurrence of `needle` starts, or `None` if it is
+        /// not contained. The index counts graphemes rather than bytes, so it can be passed to `slice`.
+        /// An empty needle is found at index 0.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let graphemes = Graphemes::from("他特别喜欢");
+        /// let needle = Graphemes::from("喜欢");
+        /// let start = graphemes.find(&needle).unwrap();
+        /// assert_eq!(start, 3);
+        /// assert_eq!(graphemes.slice(start, start + needle.len()), needle);
+        /// ```
+        pub fn find(&self, needle : &Graphemes) -> Option<usize> {
+            if needle.is_empty() {
+                return Some(0);
+            }
+            self.graphemes.windows(needle.len()).position(|window| window == &needle.graphemes[..])
+        }
+
+        /// Returns a copy with every occurrence of `from` replaced by `to`. Like `str::replace`, occurrences
+        /// are matched leftmost first without overlapping, and an empty `from` inserts `to` between all graphemes
+        /// and at both ends. Matches only start and end on grapheme boundaries.
+        ///
+        /// # Arguments
+        /// * `from` - Graphemes to replace
+        /// * `to` - Graphemes to put in their place
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let replaced = Graphemes::from("北京烤鸭").replace(&Graphemes::from("北京"), &Graphemes::from("上海"));
+        /// assert_eq!(replaced, Graphemes::from("上海烤鸭"));
+        /// ```
+        pub fn replace(&self, from : &Graphemes, to : &Graphemes<'a>) -> Graphemes<'a> {
+            let mut replaced = Graphemes::new();
+            if from.is_empty() {
+                for &grapheme in self.graphemes.iter() {
+                    replaced.extend(to.iter().cloned());
+                    replaced.push(grapheme);
+                }
+                replaced.extend(to.iter().cloned());
+                return replaced;
+            }
+            let mut start = 0;
+            while start < self.graphemes.len() {
+                if self.graphemes[start..].starts_with(&from.graphemes) {
+                    replaced.extend(to.iter().cloned());
+                    start += from.len();
+                } else {
+                    replaced.push(self.graphemes[start]);
+                    start += 1;
+                }
+            }
+            replaced
+        }
+
+        /// Returns whether the graphemes begin with `prefix`, comparing grapheme by grapheme
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert!(Graphemes::from("北京烤鸭").starts_with(&Graphemes::from("北京")));
+        /// assert!(!Graphemes::from("👨‍👩‍👧").starts_with(&Graphemes::from("👨")));
+        /// ```
+        pub fn starts_with(&self, prefix : &Graphemes) -> bool {
+            self.graphemes.starts_with(&prefix.graphemes)
+        }
+
+        /// Returns whether the graphemes end with `suffix`, comparing grapheme by grapheme
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert!(Graphemes::from("北京烤鸭").ends_with(&Graphemes::from("烤鸭")));
+        /// assert!(!Graphemes::from("cafe\u{301}").ends_with(&Graphemes::from("\u{301}")));
+        /// ```
+        pub fn ends_with(&self, suffix : &Graphemes) -> bool {
+            self.graphemes.ends_with(&suffix.graphemes)
+        }
+
+        /// Uppercases the first letter of each whitespace-delimited word and lowercases the rest.
+        /// Leading punctuation is skipped, and combining marks stay attached to their uppercased base.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from("the QUICK (brown) fox").to_title_case(), "The Quick (Brown) Fox");
+        /// ```
+        pub fn to_title_case(&self) -> String {
+            self.recase(|previous| previous.chars().all(char::is_whitespace))
+        }
+
+        /// Uppercases the first letter of each sentence and lowercases the rest.
+        /// A sentence starts at the beginning or after a whitespace following '.', '!' or '?'.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::graphemes_struct::Graphemes;
+        /// assert_eq!(Graphemes::from("hello WORLD. how are you?").to_sentence_case(), "Hello world. How are you?");
+        /// ```
+        pub fn to_sentence_case(&self) -> String {
+            let mut after_terminator = false;
+            self.recase(|previous| {
+                let starts_sentence = after_terminator && previous.chars().all(char::is_whitespace);
+                after_terminator = [".", "!", "?"].contains(&previous);
+                starts_sentence
+            })
+        }
+
+        /// Lowercases every grapheme except the first alphanumeric one at or after each boundary,
+        /// which is capitalized. `is_boundary` is called with every grapheme in order and
+        /// tells whether the grapheme following it starts a new unit.
+        fn recase<F>(&self, mut is_boundary : F) -> String
+            where F : FnMut(&str) -> bool {
+            let mut recased = String::new();
+            let mut capitalize_next = true;
+            for grapheme in self.graphemes.iter() {
+                if capitalize_next && grapheme.chars().next().is_some_and(char::is_alphanumeric) {
+                    let mut chars = grapheme.chars();
+                    recased.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+                    recased.push_str(&chars.as_str().to_lowercase());
+                    capitalize_next = false;
+                } else {
+                    recased.push_str(&grapheme.to_lowercase());
+                }
+                if is_boundary(grapheme) {
+                    capitalize_next = true;
+                }
+            }
+            recased
+        }
     }
 
     impl<'a> Display for Graphemes<'a> {
@@ -61,6 +535,25 @@ mod graphemes_struct {
         }
     }
 
+    /// Serializes as the concatenated string, so grapheme boundaries are not stored
+    #[cfg(feature = "serde")]
+    impl<'a> Serialize for Graphemes<'a> {
+        fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    /// Deserializes a string and segments it again. The graphemes borrow from the serialized input,
+    /// so deserializing fails for a string which cannot be borrowed, e.g. a JSON string containing escapes;
+    /// deserialize `OwnedGraphemes` instead for such input.
+    #[cfg(feature = "serde")]
+    impl<'de : 'a, 'a> Deserialize<'de> for Graphemes<'a> {
+        fn deserialize<D : Deserializer<'de>>(deserializer : D) -> Result<Graphemes<'a>, D::Error> {
+            let string : &'de str = Deserialize::deserialize(deserializer)?;
+            Ok(Graphemes::from(string))
+        }
+    }
+
     impl<'a> Deref for Graphemes<'a> {
         type Target = Vec<&'a str>;
 
@@ -69,6 +562,29 @@ mod graphemes_struct {
         }
     }
 
+    impl<'g, 'a> IntoIterator for &'g Graphemes<'a> {
+        type Item = &'g &'a str;
+        type IntoIter = std::slice::Iter<'g, &'a str>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.graphemes.iter()
+        }
+    }
+
+    /// Collects already segmented graphemes in order, without segmenting them again
+    impl<'a> FromIterator<&'a str> for Graphemes<'a> {
+        fn from_iter<I : IntoIterator<Item = &'a str>>(iter : I) -> Graphemes<'a> {
+            Graphemes { graphemes: iter.into_iter().collect() }
+        }
+    }
+
+    /// Appends already segmented graphemes in order, without segmenting them again
+    impl<'a> Extend<&'a str> for Graphemes<'a> {
+        fn extend<I : IntoIterator<Item = &'a str>>(&mut self, iter : I) {
+            self.graphemes.extend(iter);
+        }
+    }
+
     impl<'a> Empty for Graphemes<'a> {
         fn is_empty(&self) -> bool {
             self.graphemes.is_empty()
@@ -119,15 +635,728 @@ mod graphemes_struct {
         }
     }
 
+    /// A vector of graphemes owning their strings, for graphemes built at runtime or outliving their source string.
+    /// It can be compared with `levenshtein_distance` like `Graphemes`, and borrowed as `Graphemes` with `as_borrowed`.
+    #[derive(Debug, Default, Clone, Hash, Eq, PartialEq, PartialOrd, Ord)]
+    pub struct OwnedGraphemes {
+        graphemes : Vec<String>,
+    }
+
+    impl OwnedGraphemes {
+        pub fn new() -> OwnedGraphemes {
+            OwnedGraphemes {
+                graphemes: vec![]
+            }
+        }
+
+        /// Segments a string into graphemes like `Graphemes::from` and copies them
+        pub fn from(string : &str) -> OwnedGraphemes {
+            Graphemes::from(string).to_owned()
+        }
+
+        /// Returns graphemes borrowing from these ones
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::{dictionary_from, max_match};
+        /// use nlp::graphemes_struct::{Graphemes, OwnedGraphemes};
+        /// let sentence = OwnedGraphemes::from(&format!("{}{}", "北京", "烤鸭"));
+        /// let dictionary = dictionary_from(&["北京", "烤鸭"]);
+        /// assert_eq!(max_match(&sentence.as_borrowed(), &dictionary), Graphemes::from("北京 烤鸭"));
+        /// ```
+        pub fn as_borrowed(&self) -> Graphemes<'_> {
+            Graphemes { graphemes: self.graphemes.iter().map(String::as_str).collect() }
+        }
+    }
+
+    impl Display for OwnedGraphemes {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "{}", self.graphemes.concat())
+        }
+    }
+
+    /// Serializes as the concatenated string like `Graphemes`
+    #[cfg(feature = "serde")]
+    impl Serialize for OwnedGraphemes {
+        fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    /// Deserializes a string and segments it again, copying the string so it does not have to be borrowable
+    #[cfg(feature = "serde")]
+    impl<'de> Deserialize<'de> for OwnedGraphemes {
+        fn deserialize<D : Deserializer<'de>>(deserializer : D) -> Result<OwnedGraphemes, D::Error> {
+            let string : String = Deserialize::deserialize(deserializer)?;
+            Ok(OwnedGraphemes::from(&string))
+        }
+    }
+
+    impl Deref for OwnedGraphemes {
+        type Target = Vec<String>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.graphemes
+        }
+    }
+
+    impl Empty for OwnedGraphemes {
+        fn is_empty(&self) -> bool {
+            self.graphemes.is_empty()
+        }
+    }
+
+    impl Len for OwnedGraphemes {
+        fn len(&self) -> usize {
+            self.graphemes.len()
+        }
+    }
+
+    impl Index<usize> for OwnedGraphemes {
+        type Output = str;
+
+        fn index(&self, index : usize) -> &str {
+            &self.graphemes[index]
+        }
+    }
+
+    impl Clear for OwnedGraphemes {
+        fn clear(&mut self) {
+            self.graphemes.clear();
+        }
+    }
+
+    impl CanPush<&str> for OwnedGraphemes {
+        type PushedOut = ();
+    }
+
+    impl<'s> Push<&'s str> for OwnedGraphemes {
+        fn push(&mut self, val : &'s str) -> Option<Self::PushedOut> {
+            self.graphemes.push(val.to_string());
+            Some(())
+        }
+    }
+
+    impl<'a> PartialEq<Graphemes<'a>> for OwnedGraphemes {
+        fn eq(&self, other : &Graphemes<'a>) -> bool {
+            self.graphemes.len() == other.graphemes.len()
+                && self.graphemes.iter().zip(other.graphemes.iter()).all(|(grapheme, other_grapheme)| grapheme == other_grapheme)
+        }
+    }
+
+    impl<'a> PartialEq<OwnedGraphemes> for Graphemes<'a> {
+        fn eq(&self, other : &OwnedGraphemes) -> bool {
+            other == self
+        }
+    }
+
+    /// Printable ASCII from U+0020, sliced to return `&'static str` graphemes
+    const HALF_WIDTH_ASCII : &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+    /// Full-width forms of U+FF61 to U+FF9F, all three bytes long
+    const FULL_WIDTH_KANA : &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+    const VOICEABLE_KANA : &str = "ウカキクケコサシスセソタチツテトハヒフヘホ";
+    const VOICED_KANA : &str = "ヴガギグゲゴザジズゼゾダヂヅデドバビブベボ";
+    const SEMI_VOICEABLE_KANA : &str = "ハヒフヘホ";
+    const SEMI_VOICED_KANA : &str = "パピプペポ";
+
+    fn full_width_kana(c : char) -> Option<&'static str> {
+        if !('\u{FF61}'..='\u{FF9F}').contains(&c) {
+            return None;
+        }
+        let byte_index = (c as usize - 0xFF61) * 3;
+        Some(&FULL_WIDTH_KANA[byte_index..byte_index + 3])
+    }
+
+    /// Combines a full-width kana with a (semi-)voiced sound mark, if such a kana exists
+    fn voiced_kana(kana : &str, semi_voiced : bool) -> Option<&'static str> {
+        let (bases, voiced) = if semi_voiced { (SEMI_VOICEABLE_KANA, SEMI_VOICED_KANA) } else { (VOICEABLE_KANA, VOICED_KANA) };
+        bases.find(kana).map(|byte_index| &voiced[byte_index..byte_index + 3])
+    }
+
+    fn fold_case<'g>(graphemes : &'g [&str]) -> impl Iterator<Item = char> + 'g {
+        graphemes.iter().flat_map(|grapheme| grapheme.chars())
+            .flat_map(char::to_uppercase)
+            .flat_map(char::to_lowercase)
+    }
+
+    fn is_whitespace(grapheme : &str) -> bool {
+        grapheme.chars().all(char::is_whitespace)
+    }
+
+    /// Splits a grapheme cluster at every zero width joiner between two components
+    /// and between consecutive regional indicators
+    fn split_emoji_sequence(cluster : &str) -> Vec<&str> {
+        const ZWJ : char = '\u{200D}';
+        let is_regional_indicator = |c : char| ('\u{1F1E6}'..='\u{1F1FF}').contains(&c);
+        let mut components = vec![];
+        let mut start = 0;
+        let mut previous_regional_indicator = false;
+        for (i, c) in cluster.char_indices() {
+            let end = i + c.len_utf8();
+            if c == ZWJ && i > start && end < cluster.len() {
+                components.push(&cluster[start..i]);
+                components.push(&cluster[i..end]);
+                start = end;
+            } else if is_regional_indicator(c) && previous_regional_indicator && i > start {
+                components.push(&cluster[start..i]);
+                start = i;
+            }
+            previous_regional_indicator = is_regional_indicator(c);
+        }
+        if start < cluster.len() {
+            components.push(&cluster[start..]);
+        }
+        components
+    }
+
+    /// Number of terminal columns a grapheme occupies, clamped to 1 (narrow) or 2 (wide)
+    pub fn grapheme_width(grapheme : &str) -> usize {
+        UnicodeWidthStr::width(grapheme).clamp(1, 2)
+    }
+
+    /// What `wrap_ranges` needs to know about a grapheme, or anything else laid out like one
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum WrapCell {
+        /// Text taking a number of columns, never dropped
+        Text(usize),
+        /// Whitespace taking a number of columns, where lines are preferably broken
+        Space(usize),
+        /// A line break
+        Newline,
+    }
+
+    impl WrapCell {
+        pub fn of(grapheme : &str) -> WrapCell {
+            if grapheme == "\n" || grapheme == "\r\n" {
+                WrapCell::Newline
+            } else if is_whitespace(grapheme) {
+                WrapCell::Space(grapheme_width(grapheme))
+            } else {
+                WrapCell::Text(grapheme_width(grapheme))
+            }
+        }
+
+        pub fn columns(&self) -> usize {
+            match *self {
+                WrapCell::Text(columns) | WrapCell::Space(columns) => columns,
+                WrapCell::Newline => 0,
+            }
+        }
+    }
+
+    /// Breaks cells into lines of at most `width` columns, returned as (start, end) ranges of cells.
+    /// This is the policy of `Graphemes::wrap`: lines are broken at the last space that fits, or between
+    /// cells when there is none, and at every newline. A cell wider than `width` gets a line of its own.
+    /// Spaces at the end of a line and at soft line breaks are left out of the ranges.
+    pub fn wrap_ranges(cells : &[WrapCell], width : usize) -> Vec<(usize, usize)> {
+        let trim_end = |start : usize, mut end : usize| {
+            while end > start && matches!(cells[end - 1], WrapCell::Space(_)) {
+                end -= 1;
+            }
+            (start, end)
+        };
+        let mut lines = vec![];
+        let mut line_start = 0;
+        let mut line_width = 0;
+        let mut last_space = None;
+        let mut after_soft_break = false;
+        for (i, &cell) in cells.iter().enumerate() {
+            let (columns, space) = match cell {
+                WrapCell::Newline => {
+                    lines.push(trim_end(line_start, i));
+                    line_start = i + 1;
+                    line_width = 0;
+                    last_space = None;
+                    after_soft_break = false;
+                    continue;
+                }
+                WrapCell::Space(columns) => (columns, true),
+                WrapCell::Text(columns) => (columns, false),
+            };
+            if after_soft_break && space {
+                line_start = i + 1;
+                continue;
+            }
+            after_soft_break = false;
+            if line_width + columns > width && i > line_start {
+                let break_at = if space { Some(i) } else { last_space };
+                if let Some(break_at) = break_at {
+                    let (start, end) = trim_end(line_start, break_at);
+                    if end > start {
+                        lines.push((start, end));
+                    }
+                    line_start = break_at + 1;
+                    last_space = None;
+                    if space {
+                        line_width = 0;
+                        after_soft_break = true;
+                        continue;
+                    }
+                    line_width = cells[line_start..i].iter().map(WrapCell::columns).sum();
+                }
+                if line_width + columns > width && i > line_start {
+                    lines.push((line_start, i));
+                    line_start = i;
+                    line_width = 0;
+                }
+            }
+            if space {
+                last_space = Some(i);
+            }
+            line_width += columns;
+        }
+        let (start, end) = trim_end(line_start, cells.len());
+        if end > start {
+            lines.push((start, end));
+        }
+        lines
+    }
+
 
 }
 
 #[cfg(test)]
 mod test_cases {
-    use super::graphemes_struct::Graphemes;
+    use super::graphemes_struct::{Graphemes, OwnedGraphemes, NormalizationConfig};
+    use crate::metrics::levenshtein_distance;
+    use len_trait::len::{Len, Empty, Clear};
+    use super::Script;
+    use unicode_normalization::UnicodeNormalization;
+    use std::collections::{HashMap, HashSet};
+    use push_trait::base::Push;
 
     #[test]
     fn graphemes_split_test() {
         assert_eq!(Graphemes::from("hello world").split(" "), vec![Graphemes::from("hello"), Graphemes::from("world")])
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn graphemes_clone_test() {
+        let mut graphemes = Graphemes::from("北京");
+        let copy = graphemes.clone();
+        graphemes.push("烤");
+        assert_eq!(copy, Graphemes::from("北京"));
+        assert_ne!(copy, graphemes);
+    }
+
+    #[test]
+    fn graphemes_ord_test() {
+        let mut words : Vec<Graphemes> = ["banana", "apple", "cherry"].iter().map(|word| Graphemes::from(word)).collect();
+        words.sort();
+        assert_eq!(words, vec![Graphemes::from("apple"), Graphemes::from("banana"), Graphemes::from("cherry")]);
+        assert_eq!(words.binary_search(&Graphemes::from("banana")), Ok(1));
+        assert_eq!(words.binary_search(&Graphemes::from("blueberry")), Err(2));
+        // a prefix comes first, and uppercase before lowercase in code point order
+        assert!(Graphemes::from("北京") < Graphemes::from("北京烤鸭"));
+        assert!(Graphemes::from("Zebra") < Graphemes::from("apple"));
+        assert!(Graphemes::from("") < Graphemes::from("a"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn graphemes_serde_test() {
+        let cafe = Graphemes::from("cafe\u{301}");
+        assert_eq!(cafe.len(), 4);
+        let json = serde_json::to_string(&cafe).unwrap();
+        assert_eq!(json, "\"cafe\u{301}\"");
+        let deserialized : Graphemes = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, cafe);
+        assert_eq!(deserialized[3], "e\u{301}");
+        let words : Vec<Graphemes> = serde_json::from_str(r#"["北京", "烤鸭"]"#).unwrap();
+        assert_eq!(words, vec![Graphemes::from("北京"), Graphemes::from("烤鸭")]);
+        // escaped strings cannot be borrowed, but can be owned
+        assert!(serde_json::from_str::<Graphemes>(r#""a\nb""#).is_err());
+        let owned : OwnedGraphemes = serde_json::from_str(r#""e\u0301\n""#).unwrap();
+        assert_eq!(owned, Graphemes::from("e\u{301}\n"));
+        assert_eq!(serde_json::to_string(&owned).unwrap(), serde_json::to_string(&owned.as_borrowed()).unwrap());
+    }
+
+    #[test]
+    fn graphemes_into_iter_test() {
+        let graphemes = Graphemes::from("a̐éö");
+        let mut clusters : Vec<&str> = vec![];
+        for grapheme in &graphemes {
+            clusters.push(grapheme);
+        }
+        assert_eq!(clusters, vec!["a̐", "é", "ö"]);
+        assert_eq!((&graphemes).into_iter().cloned().collect::<Vec<&str>>(), vec!["a̐", "é", "ö"]);
+        assert_eq!(graphemes.iter().count(), 3);
+        assert_eq!((&Graphemes::from("")).into_iter().next(), None);
+    }
+
+    #[test]
+    fn graphemes_from_iter_test() {
+        let graphemes : Graphemes = vec!["h", "e", "l", "l", "o"].into_iter().collect();
+        assert_eq!(graphemes.len(), 5);
+        assert_eq!(graphemes.to_string(), "hello");
+        assert_eq!(graphemes, Graphemes::from("hello"));
+        // graphemes are taken as given
+        let joined : Graphemes = vec!["北京", "烤鸭"].into_iter().collect();
+        assert_eq!(joined.len(), 2);
+        assert!(std::iter::empty::<&str>().collect::<Graphemes>().is_empty());
+    }
+
+    #[test]
+    fn graphemes_extend_test() {
+        let mut graphemes = Graphemes::from("he");
+        graphemes.extend(vec!["l", "l", "o"]);
+        assert_eq!(graphemes.to_string(), "hello");
+        graphemes.extend(Graphemes::from(" wörld").iter().cloned());
+        assert_eq!(graphemes, Graphemes::from("hello wörld"));
+        graphemes.extend(std::iter::empty());
+        assert_eq!(graphemes.len(), 11);
+    }
+
+    #[test]
+    fn graphemes_contains_test() {
+        let graphemes = Graphemes::from("北京烤鸭");
+        assert!(graphemes.contains(&Graphemes::from("京烤")));
+        assert!(graphemes.contains(&Graphemes::from("北京烤鸭")));
+        assert!(graphemes.contains(&Graphemes::from("")));
+        assert!(Graphemes::from("").contains(&Graphemes::from("")));
+        assert!(!graphemes.contains(&Graphemes::from("京鸭")));
+        assert!(!graphemes.contains(&Graphemes::from("北京烤鸭!")));
+        // combining marks belong to the cluster of their base
+        let decomposed = "cafe\u{301} au lait";
+        assert!(decomposed.contains("e"));
+        assert!(!Graphemes::from(decomposed).contains(&Graphemes::from("e")));
+        assert!(Graphemes::from(decomposed).contains(&Graphemes::from("e\u{301} a")));
+        // so do the members of an emoji sequence
+        let family = "👨‍👩‍👧 and 👩";
+        assert!(family.contains("👨"));
+        assert!(!Graphemes::from(family).contains(&Graphemes::from("👨")));
+        assert!(Graphemes::from(family).contains(&Graphemes::from("👩")));
+    }
+
+    #[test]
+    fn graphemes_starts_ends_with_test() {
+        let graphemes = Graphemes::from("北京烤鸭");
+        assert!(graphemes.starts_with(&Graphemes::from("北")));
+        assert!(graphemes.starts_with(&Graphemes::from("北京烤鸭")));
+        assert!(graphemes.starts_with(&Graphemes::from("")));
+        assert!(!graphemes.starts_with(&Graphemes::from("京")));
+        assert!(!graphemes.starts_with(&Graphemes::from("北京烤鸭店")));
+        assert!(graphemes.ends_with(&Graphemes::from("鸭")));
+        assert!(graphemes.ends_with(&Graphemes::from("")));
+        assert!(!graphemes.ends_with(&Graphemes::from("烤")));
+        assert!(!graphemes.ends_with(&Graphemes::from("吃北京烤鸭")));
+        assert!(Graphemes::from("").starts_with(&Graphemes::from("")));
+        assert!(!Graphemes::from("").ends_with(&Graphemes::from("鸭")));
+        // a prefix of an emoji sequence is not a prefix of its cluster
+        let family = Graphemes::from("👨‍👩‍👧👍🏽");
+        assert!("👨‍👩‍👧👍🏽".starts_with("👨‍👩"));
+        assert!(!family.starts_with(&Graphemes::from("👨‍👩")));
+        assert!(family.starts_with(&Graphemes::from("👨‍👩‍👧")));
+        assert!(family.starts_with(&Graphemes::from("👨‍👩‍👧👍🏽")));
+        assert!("👨‍👩‍👧👍🏽".ends_with("🏽"));
+        assert!(!family.ends_with(&Graphemes::from("🏽")));
+        assert!(family.ends_with(&Graphemes::from("👍🏽")));
+    }
+
+    #[test]
+    fn graphemes_find_test() {
+        let graphemes = Graphemes::from("他特别喜欢");
+        assert_eq!(graphemes.find(&Graphemes::from("喜欢")), Some(3));
+        assert_eq!(graphemes.find(&Graphemes::from("他")), Some(0));
+        assert_eq!(graphemes.find(&Graphemes::from("")), Some(0));
+        assert_eq!(graphemes.find(&Graphemes::from("欢喜")), None);
+        assert_eq!(Graphemes::from("").find(&Graphemes::from("他")), None);
+        // the first occurrence wins
+        assert_eq!(Graphemes::from("abab").find(&Graphemes::from("ab")), Some(0));
+        // indices count graphemes, not bytes
+        let mixed = Graphemes::from("e\u{301}👨‍👩‍👧北京");
+        assert_eq!("e\u{301}👨‍👩‍👧北京".find("北京"), Some(21));
+        assert_eq!(mixed.find(&Graphemes::from("北京")), Some(2));
+        assert_eq!(mixed.slice(2, 4), Graphemes::from("北京"));
+        assert_eq!(mixed.find(&Graphemes::from("👩")), None);
+    }
+
+    #[test]
+    fn graphemes_replace_test() {
+        let graphemes = Graphemes::from("北京烤鸭");
+        let replaced = graphemes.replace(&Graphemes::from("北京"), &Graphemes::from("上海"));
+        assert_eq!(replaced, Graphemes::from("上海烤鸭"));
+        assert_eq!(replaced.len(), 4);
+        assert_eq!(graphemes.replace(&Graphemes::from("烤"), &Graphemes::from("")), Graphemes::from("北京鸭"));
+        assert_eq!(graphemes.replace(&Graphemes::from("南京"), &Graphemes::from("上海")), graphemes);
+        // leftmost, non-overlapping matches like str::replace
+        for &(string, from, to) in [("aaa", "aa", "b"), ("abab", "ab", "ba"), ("aaaa", "aa", "a"), ("ab", "", "-"), ("", "", "-"), ("", "a", "b")].iter() {
+            let replaced = Graphemes::from(string).replace(&Graphemes::from(from), &Graphemes::from(to));
+            assert_eq!(replaced.to_string(), string.replace(from, to));
+        }
+        // emoji sequences are never split
+        let family = "👨‍👩‍👧 and 👩";
+        assert_eq!(family.replace("👩", "🐱"), "👨‍🐱‍👧 and 🐱");
+        let replaced = Graphemes::from(family).replace(&Graphemes::from("👩"), &Graphemes::from("🐱"));
+        assert_eq!(replaced, Graphemes::from("👨‍👩‍👧 and 🐱"));
+    }
+
+    #[test]
+    fn owned_graphemes_test() {
+        let words : Vec<OwnedGraphemes> = (1..=3).map(|count| OwnedGraphemes::from(&"北京".repeat(count))).collect();
+        assert_eq!(levenshtein_distance(&words[0], &words[2], 1), 4);
+        assert_eq!(levenshtein_distance(&OwnedGraphemes::from(&format!("{}en", "kitt")), &OwnedGraphemes::from("sitting"), 1), 3);
+        assert_eq!(levenshtein_distance(&OwnedGraphemes::from("cafe\u{301}"), &OwnedGraphemes::from("cafe"), 1), 1);
+
+        let mut built = OwnedGraphemes::new();
+        for grapheme in ["北", "京"].iter() {
+            built.push(grapheme);
+        }
+        assert_eq!(built, Graphemes::from("北京"));
+        assert_eq!(Graphemes::from("北京"), built);
+        assert_ne!(built, Graphemes::from("北京烤鸭"));
+        assert_eq!(built.len(), 2);
+        assert_eq!(&built[1], "京");
+        assert_eq!(built.to_string(), "北京");
+        assert_eq!(built.as_borrowed(), Graphemes::from("北京"));
+        assert_eq!(built.as_borrowed().to_owned(), built);
+        built.clear();
+        assert!(built.is_empty());
+        assert_eq!(built, OwnedGraphemes::default());
+    }
+
+    #[test]
+    fn graphemes_split_any_test() {
+        let separators : HashSet<&str> = [",", " ", "，"].iter().cloned().collect();
+        assert_eq!(Graphemes::from("a,b c，d").split_any(&separators),
+                   vec![Graphemes::from("a"), Graphemes::from("b"), Graphemes::from("c"), Graphemes::from("d")]);
+        assert_eq!(Graphemes::from("a, b").split_any(&separators), vec![Graphemes::from("a"), Graphemes::new(), Graphemes::from("b")]);
+        assert_eq!(Graphemes::from("北京烤鸭").split_any(&separators), vec![Graphemes::from("北京烤鸭")]);
+        assert_eq!(Graphemes::from("a b").split_any(&HashSet::new()), vec![Graphemes::from("a b")]);
+    }
+
+    #[test]
+    fn graphemes_map_graphemes_test() {
+        let leet : HashMap<&str, &str> = [("4", "a"), ("3", "e"), ("1", "l"), ("0", "o"), ("7", "t")].iter().cloned().collect();
+        let unleet = |grapheme| *leet.get(grapheme).unwrap_or(&grapheme);
+        assert_eq!(Graphemes::from("h3ll0 w0r1d").map_graphemes(unleet), Graphemes::from("hello world"));
+        assert_eq!(Graphemes::from("1337 北京").map_graphemes(unleet), Graphemes::from("leet 北京"));
+        // graphemes stay whole
+        assert_eq!(Graphemes::from("a̐1").map_graphemes(unleet), Graphemes::from("a̐l"));
+        assert!(Graphemes::from("").map_graphemes(unleet).is_empty());
+    }
+
+    #[test]
+    fn graphemes_eq_ignore_case_test() {
+        assert!(Graphemes::from("Hello World").eq_ignore_case(&Graphemes::from("hELLO wORLD")));
+        assert!(Graphemes::from("ÉCOLE").eq_ignore_case(&Graphemes::from("école")));
+        assert!(Graphemes::from("ΣΊΣΥΦΟΣ").eq_ignore_case(&Graphemes::from("σίσυφος")));
+        assert!(Graphemes::from("北京").eq_ignore_case(&Graphemes::from("北京")));
+        assert!(!Graphemes::from("hello").eq_ignore_case(&Graphemes::from("help")));
+        assert!(!Graphemes::from("a").eq_ignore_case(&Graphemes::from("a̐")));
+        // no Turkish tailoring: "İ" folds to "i̇" and "I" to "i"
+        assert!(!Graphemes::from("İ").eq_ignore_case(&Graphemes::from("i")));
+        assert!(Graphemes::from("İ").eq_ignore_case(&Graphemes::from("i\u{307}")));
+        // dotless "ı" uppercases to "I", so it equals "i"
+        assert!(Graphemes::from("ı").eq_ignore_case(&Graphemes::from("i")));
+        assert!(Graphemes::from("").eq_ignore_case(&Graphemes::from("")));
+    }
+
+    #[test]
+    fn graphemes_byte_offsets_test() {
+        let string = "hi 👋🏽 e\u{301}!";
+        let graphemes = Graphemes::from(string);
+        let offsets = graphemes.byte_offsets();
+        // the waving hand and its skin tone modifier are 4 bytes each, the combining acute accent is 2
+        assert_eq!(offsets, vec![0, 1, 2, 3, 11, 12, 15, 16]);
+        for (i, grapheme) in graphemes.iter().enumerate() {
+            assert_eq!(&string[offsets[i]..offsets[i+1]], *grapheme);
+        }
+        assert_eq!(*offsets.last().unwrap(), string.len());
+        assert_eq!(Graphemes::from("").byte_offsets(), vec![0]);
+    }
+
+    #[test]
+    fn graphemes_retain_test() {
+        let mut graphemes = Graphemes::from("a1!b2?");
+        graphemes.retain(|grapheme| grapheme.chars().all(char::is_alphanumeric));
+        assert_eq!(graphemes, Graphemes::from("a1b2"));
+        // combining marks are kept or dropped with their base
+        let mut accented = Graphemes::from("e\u{301}-a\u{310}");
+        accented.retain(|grapheme| grapheme != "-");
+        assert_eq!(accented.len(), 2);
+        assert_eq!(accented.to_string(), "e\u{301}a\u{310}");
+        let mut visited = vec![];
+        accented.retain(|grapheme| { visited.push(grapheme.to_string()); false });
+        assert_eq!(visited, vec!["e\u{301}", "a\u{310}"]);
+        assert!(accented.is_empty());
+    }
+
+    #[test]
+    fn graphemes_rle_test() {
+        let graphemes = Graphemes::from("aaabbc");
+        let runs = graphemes.rle();
+        assert_eq!(runs, vec![("a", 3), ("b", 2), ("c", 1)]);
+        assert_eq!(Graphemes::from_rle(&runs), graphemes);
+        // runs are made of whole graphemes
+        assert_eq!(Graphemes::from("e\u{301}e\u{301}e北北").rle(), vec![("e\u{301}", 2), ("e", 1), ("北", 2)]);
+        assert_eq!(Graphemes::from_rle(&[("a", 0), ("b", 2)]), Graphemes::from("bb"));
+        assert!(Graphemes::from("").rle().is_empty());
+        assert!(Graphemes::from_rle(&[]).is_empty());
+    }
+
+    #[test]
+    fn graphemes_scripts_test() {
+        let scripts = Graphemes::from("I like 北京烤鸭, ĉu? Да!").scripts();
+        let (latin, han, cyrillic, common) = (Script::Latin, Script::Han, Script::Cyrillic, Script::Common);
+        assert_eq!(scripts, vec![
+            latin, common, latin, latin, latin, latin, common,
+            han, han, han, han, common, common,
+            latin, latin, common, common,
+            cyrillic, cyrillic, common,
+        ]);
+        // combining marks take the script of their base
+        assert_eq!(Graphemes::from("a̐é").scripts(), vec![latin, latin]);
+        assert_eq!(Graphemes::from("\u{301}").scripts(), vec![Script::Inherited]);
+        assert_eq!(Graphemes::from("ひらがなカタカナ").scripts()[..5], [Script::Hiragana, Script::Hiragana, Script::Hiragana, Script::Hiragana, Script::Katakana]);
+        assert!(Graphemes::from("").scripts().is_empty());
+    }
+
+    #[test]
+    fn graphemes_count_matches_test() {
+        let is_digit = |grapheme : &str| grapheme.chars().all(|c| c.is_ascii_digit());
+        assert_eq!(Graphemes::from("a1b2c3").count_matches(is_digit), 3);
+        assert_eq!(Graphemes::from("").count_matches(is_digit), 0);
+        let is_cjk = |grapheme : &str| grapheme.chars().all(|c| ('\u{4E00}'..='\u{9FFF}').contains(&c));
+        assert_eq!(Graphemes::from("他likes北京烤鸭!").count_matches(is_cjk), 5);
+    }
+
+    #[test]
+    fn graphemes_reversed_test() {
+        let graphemes = Graphemes::from("a̐éö");
+        assert_eq!(graphemes.reversed(), Graphemes::from("öéa̐"));
+        assert_eq!(graphemes, Graphemes::from("a̐éö"));
+    }
+
+    #[test]
+    fn graphemes_palindrome_distance_test() {
+        assert_eq!(Graphemes::from("racecar").palindrome_distance(), 0);
+        assert_eq!(Graphemes::from("").palindrome_distance(), 0);
+        assert_eq!(Graphemes::from("ab").palindrome_distance(), 1);
+        assert_eq!(Graphemes::from("abca").palindrome_distance(), 1);
+        assert_eq!(Graphemes::from("abcd").palindrome_distance(), 3);
+        assert_eq!(Graphemes::from("上海自来水来自海上").palindrome_distance(), 0);
+    }
+
+    #[test]
+    fn graphemes_chunks_test() {
+        let graphemes = Graphemes::from("北京烤鸭a̐éö");
+        assert_eq!(graphemes.chunks(3), vec![Graphemes::from("北京烤"), Graphemes::from("鸭a̐é"), Graphemes::from("ö")]);
+        assert_eq!(graphemes.chunks(7), vec![Graphemes::from("北京烤鸭a̐éö")]);
+        assert!(Graphemes::from("").chunks(3).is_empty());
+    }
+
+    #[test]
+    fn graphemes_windows_test() {
+        let graphemes = Graphemes::from("北京烤鸭a̐éö");
+        assert_eq!(graphemes.windows(3), vec![
+            Graphemes::from("北京烤"), Graphemes::from("京烤鸭"), Graphemes::from("烤鸭a̐"),
+            Graphemes::from("鸭a̐é"), Graphemes::from("a̐éö")]);
+        assert!(graphemes.windows(8).is_empty());
+    }
+
+    #[test]
+    fn graphemes_title_case_test() {
+        assert_eq!(Graphemes::from("we can ONLY see").to_title_case(), "We Can Only See");
+        assert_eq!(Graphemes::from("  it's a̐pple\tpie ").to_title_case(), "  It's A̐pple\tPie ");
+        assert_eq!(Graphemes::from("éCOLE über").to_title_case(), "École Über");
+        assert_eq!(Graphemes::from("北京烤鸭 is tasty").to_title_case(), "北京烤鸭 Is Tasty");
+        assert_eq!(Graphemes::from("").to_title_case(), "");
+    }
+
+    #[test]
+    fn graphemes_sentence_case_test() {
+        assert_eq!(Graphemes::from("WE CAN. only see! a̐head? yes").to_sentence_case(), "We can. Only see! A̐head? Yes");
+        assert_eq!(Graphemes::from("version 1.5 IS out").to_sentence_case(), "Version 1.5 is out");
+        assert_eq!(Graphemes::from("北京烤鸭. great").to_sentence_case(), "北京烤鸭. Great");
+        assert_eq!(Graphemes::from("").to_sentence_case(), "");
+    }
+
+    #[test]
+    fn graphemes_from_normalized_test() {
+        // the angstrom sign is replaced by its canonical equivalent Å
+        let string = "Ｃafe\u{301} ＳTRAßE ｶﾀｶﾅ \u{212B}";
+        let config = NormalizationConfig { ignore_case: true, normalize_width: true };
+        let normalized = Graphemes::from_normalized(string, config);
+        // the same normalizations done step by step with intermediate strings
+        let nfc : String = string.nfc().collect();
+        let folded : String = nfc.chars().flat_map(char::to_uppercase).flat_map(char::to_lowercase).collect::<String>().nfc().collect();
+        assert_eq!(normalized, Graphemes::from(&folded).normalize_width());
+        assert_eq!(normalized.to_string(), "café strasse ｶﾀｶﾅ å");
+
+        let nfc_only = Graphemes::from_normalized(string, NormalizationConfig::default());
+        assert_eq!(nfc_only, Graphemes::from(&nfc));
+        assert_eq!(nfc_only.len(), Graphemes::from(string).len());
+        assert!(Graphemes::from_normalized("", config).is_empty());
+    }
+
+    #[test]
+    fn graphemes_emoji_aware_test() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+        assert_eq!(Graphemes::from(family).len(), 1);
+        assert_eq!(Graphemes::from_emoji_aware(family, true), Graphemes::from(family));
+        let components = Graphemes::from_emoji_aware(family, false);
+        assert_eq!(&components[..], &["👨", "\u{200D}", "👩", "\u{200D}", "👧"]);
+        assert_eq!(components.to_string(), family);
+
+        let flags = "🇯🇵🇫🇷";
+        assert_eq!(&Graphemes::from_emoji_aware(flags, true)[..], &["🇯🇵", "🇫🇷"]);
+        assert_eq!(&Graphemes::from_emoji_aware(flags, false)[..], &["🇯", "🇵", "🇫", "🇷"]);
+
+        // other clusters are unaffected
+        let text = "a̐é 北京";
+        assert_eq!(Graphemes::from_emoji_aware(text, false), Graphemes::from(text));
+    }
+
+    #[test]
+    fn graphemes_trim_test() {
+        let graphemes = Graphemes::from("\u{3000}\u{3000} 北京 烤鸭\t\u{3000}\r\n");
+        assert_eq!(graphemes.trim(), Graphemes::from("北京 烤鸭"));
+        assert_eq!(graphemes.trim_start(), Graphemes::from("北京 烤鸭\t\u{3000}\r\n"));
+        assert_eq!(graphemes.trim_end(), Graphemes::from("\u{3000}\u{3000} 北京 烤鸭"));
+        assert_eq!(Graphemes::from("a̐é").trim(), Graphemes::from("a̐é"));
+        assert!(Graphemes::from("\u{3000} \u{3000}").trim().is_empty());
+        assert!(Graphemes::from("\u{3000} \u{3000}").trim_end().is_empty());
+        assert!(Graphemes::from("").trim_start().is_empty());
+    }
+
+    #[test]
+    fn graphemes_normalize_width_test() {
+        assert_eq!(Graphemes::from("Ａ１").normalize_width(), Graphemes::from("A1"));
+        assert_eq!(Graphemes::from("ｈｅｌｌｏ，　ｗｏｒｌｄ！～").normalize_width(), Graphemes::from("hello, world!~"));
+        assert_eq!(Graphemes::from("北京２０２２").normalize_width(), Graphemes::from("北京2022"));
+        // half-width kana and other graphemes are left alone
+        assert_eq!(Graphemes::from("ｶﾀｶﾅ a̐").normalize_width(), Graphemes::from("ｶﾀｶﾅ a̐"));
+        assert!(Graphemes::from("").normalize_width().is_empty());
+    }
+
+    #[test]
+    fn graphemes_widen_kana_test() {
+        assert_eq!(Graphemes::from("ｶﾀｶﾅ").widen_kana(), Graphemes::from("カタカナ"));
+        assert_eq!(Graphemes::from("ｶﾞｷﾞﾊﾟﾋﾟｳﾞ").widen_kana(), Graphemes::from("ガギパピヴ"));
+        assert_eq!(Graphemes::from("｢ｺｰﾋｰ｣｡").widen_kana(), Graphemes::from("「コーヒー」。"));
+        // a sound mark which cannot combine is kept as is
+        assert_eq!(Graphemes::from("ｱﾞ").widen_kana(), Graphemes::from("ｱﾞ"));
+        assert_eq!(Graphemes::from("Ａ1").widen_kana(), Graphemes::from("Ａ1"));
+    }
+
+    #[test]
+    fn graphemes_wrap_test() {
+        let lines = |text : &str, width : usize| -> Vec<String> {
+            Graphemes::from(text).wrap(width).iter().map(|line| line.to_string()).collect()
+        };
+        assert_eq!(lines("we can only see a short distance ahead", 10), vec!["we can", "only see a", "short", "distance", "ahead"]);
+        // CJK graphemes take two columns
+        assert_eq!(lines("我爱北京 and 烤鸭", 6), vec!["我爱北", "京 and", "烤鸭"]);
+        assert_eq!(lines("我爱北京", 3), vec!["我", "爱", "北", "京"]);
+        // a grapheme wider than a line is never split
+        assert_eq!(lines("北京", 1), vec!["北", "京"]);
+        assert_eq!(lines("a̐éö a̐éö", 3), vec!["a̐éö", "a̐éö"]);
+        // words longer than a line are broken between graphemes
+        assert_eq!(lines("abcdefgh ij", 3), vec!["abc", "def", "gh", "ij"]);
+        // whitespace around soft breaks is dropped, newlines always break
+        assert_eq!(lines("ab   cd", 2), vec!["ab", "cd"]);
+        assert_eq!(lines("ab\n\ncd ef", 5), vec!["ab", "", "cd ef"]);
+        assert!(lines("", 4).is_empty());
+    }
+}
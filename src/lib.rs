@@ -1,130 +1,1573 @@
 use std::collections::{HashMap, HashSet};
-use crate::graphemes_struct::Graphemes;
+use crate::graphemes_struct::{Graphemes, grapheme_width, WrapCell, wrap_ranges};
+use crate::metrics::cost_matrix;
 use len_trait::len::Len;
 use std::ops::Index;
+use len_trait::len::Empty;
 use push_trait::base::Push;
 use itertools::Itertools;
 
 pub mod graphemes_struct;
 pub mod metrics;
+pub mod fuzzy;
+pub mod tokenizer;
+pub mod stemmer;
+pub mod cluster;
+pub mod diff;
+pub mod segmenter;
+
+pub use crate::metrics::levenshtein_distance;
 
 type Coordinate = (usize, usize);
 
+/// An indexable sequence of comparable items, such as `Graphemes` or `Vec<Graphemes>`.
+/// The distance and alignment functions accept any `Sequence`.
+///
+/// # Example
+/// ```
+/// use nlp::Sequence;
+/// use nlp::metrics::levenshtein_distance;
+/// use nlp::graphemes_struct::Graphemes;
+///
+/// fn distance_to_empty<T : Sequence + Default>(sequence : &T) -> usize {
+///     levenshtein_distance(sequence, &T::default(), 1)
+/// }
+/// assert_eq!(distance_to_empty(&Graphemes::from("北京烤鸭")), 4);
+/// assert_eq!(distance_to_empty(&vec![Graphemes::from("北京"), Graphemes::from("烤鸭")]), 2);
+/// ```
+pub trait Sequence : Len + Index<usize, Output = <Self as Sequence>::Item> {
+    type Item : PartialEq + ?Sized;
+}
+
+impl<T> Sequence for T where T : Len + Index<usize> + ?Sized, T::Output : PartialEq {
+    type Item = T::Output;
+}
+
 /// Returns the backtraced path as a vector of coordinates (row, col) from the levenshtein distance cost matrix
-/// starting at `(0, 0)`
+/// starting at `(0, 0)`.
+/// The path is backtraced from the end, and when several steps into a cell are equally cheap
+/// a substitution (or match) is taken first, then a deletion, then an insertion, so gaps end up as early as possible.
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::alignment_path;
+/// use nlp::graphemes_struct::Graphemes;
+///
+/// alignment_path(&Graphemes::from("dog"), &Graphemes::from("woof"), 1);
+/// // returns [(0, 0), (0, 1), (1, 2), (2, 3), (3, 4)]
+/// ```
+pub fn alignment_path<T>(graphemes1 : &T, graphemes2: &T, sub_cost : usize) -> Vec<Coordinate>
+    where T : Sequence + ?Sized {
+    alignment_path_with(graphemes1, graphemes2, &AlignmentConfig { sub_cost, ..AlignmentConfig::default() })
+}
+
+/// Options of `alignment_path_with` and `alignment_strings_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignmentConfig {
+    /// Cost of substituting a character with another, 1 by default
+    pub sub_cost : usize,
+    /// Whether a substitution is chosen over an insertion and a deletion of equal cost.
+    /// On by default, breaking ties by substitution, then deletion, then insertion. When off, ties are broken
+    /// by deletion, then insertion, then substitution, so with a `sub_cost` of 2 or more differing graphemes
+    /// are never aligned with each other
+    pub prefer_substitution : bool,
+}
+
+impl Default for AlignmentConfig {
+    fn default() -> AlignmentConfig {
+        AlignmentConfig { sub_cost: 1, prefer_substitution: true }
+    }
+}
+
+/// Returns the backtraced path like `alignment_path`, with the cost and tie-breaking given by `config`
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `config` - Substitution cost and tie-break preference
+///
+/// # Example
+/// ```
+/// use nlp::{alignment_path_with, AlignmentConfig};
+/// use nlp::graphemes_struct::Graphemes;
+/// let config = AlignmentConfig { sub_cost: 2, prefer_substitution: true };
+/// assert_eq!(alignment_path_with(&Graphemes::from("a"), &Graphemes::from("b"), &config), vec![(0, 0), (1, 1)]);
+/// ```
+pub fn alignment_path_with<T>(graphemes1 : &T, graphemes2 : &T, config : &AlignmentConfig) -> Vec<Coordinate>
+    where T : Sequence + ?Sized {
+    let mat = alignment_matrix(graphemes1, graphemes2, config.sub_cost, config.prefer_substitution);
+    let mut path = backtrace_alignment_matrix((graphemes1.len(), graphemes2.len()), mat);
+    path.reverse();
+    path
+}
+
+/// Returns the backtraced path like `alignment_path`, but forced through the given anchors.
+/// Each anchor `(i, j)` fixes `graphemes1[i]` to be aligned with `graphemes2[j]`, and the
+/// segments between consecutive anchors are aligned independently.
+///
+/// # Panics
+/// Panics if the anchors are not strictly increasing in both indices, or an anchor is out of range of either sequence.
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+/// * `anchors` - Known correspondences `(index in graphemes1, index in graphemes2)`, strictly increasing in both
+///
+/// # Example
+/// ```
+/// use nlp::alignment_with_anchors;
+/// use nlp::graphemes_struct::Graphemes;
+///
+/// let path = alignment_with_anchors(&Graphemes::from("abc"), &Graphemes::from("xbz"), 1, &[(1, 1)]);
+/// assert!(path.contains(&(1, 1)) && path.contains(&(2, 2)));
+/// ```
+pub fn alignment_with_anchors<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, anchors : &[Coordinate]) -> Vec<Coordinate>
+    where T : Sequence + ?Sized {
+    let mut path = vec![(0, 0)];
+    let mut segment_start = (0, 0);
+    let segment_ends = anchors.iter().cloned().chain(std::iter::once((graphemes1.len(), graphemes2.len())));
+    for (anchor_index, segment_end) in segment_ends.enumerate() {
+        let is_anchor = anchor_index < anchors.len();
+        if segment_end.0 < segment_start.0 || segment_end.1 < segment_start.1
+            || (is_anchor && (segment_end.0 >= graphemes1.len() || segment_end.1 >= graphemes2.len())) {
+            panic!("anchors must be strictly increasing and within both sequences");
+        }
+        let sub1 = SubSequence { sequence: graphemes1, start: segment_start.0, end: segment_end.0 };
+        let sub2 = SubSequence { sequence: graphemes2, start: segment_start.1, end: segment_end.1 };
+        let segment_path = alignment_path(&sub1, &sub2, sub_cost);
+        path.extend(segment_path.iter().skip(1).map(|&(row, col)| (row + segment_start.0, col + segment_start.1)));
+        if is_anchor {
+            segment_start = (segment_end.0 + 1, segment_end.1 + 1);
+            path.push(segment_start);
+        }
+    }
+    path
+}
+
+/// Returns the backtraced path of an optimal alignment like `alignment_path`, among the alignments
+/// where no run of consecutive insertions or consecutive deletions is longer than `max_gap`,
+/// or `None` if there is no such alignment
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+/// * `max_gap` - Longest allowed run of insertions or deletions
+///
+/// # Example
+/// ```
+/// use nlp::alignment_max_gap;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(alignment_max_gap(&Graphemes::from("ab"), &Graphemes::from("axxb"), 1, 2), Some(vec![(0, 0), (1, 1), (1, 2), (1, 3), (2, 4)]));
+/// assert_eq!(alignment_max_gap(&Graphemes::from("ab"), &Graphemes::from("axxxxb"), 1, 1), None);
+/// ```
+pub fn alignment_max_gap<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, max_gap : usize) -> Option<Vec<Coordinate>>
+    where T : Sequence + ?Sized {
+    let num_rows = graphemes1.len() + 1;
+    let num_cols = graphemes2.len() + 1;
+    // state 0: the last step is diagonal, state k in 1..=max_gap: the last k steps are insertions,
+    // state max_gap + k: the last k steps are deletions
+    let num_states = 2 * max_gap + 1;
+    let mut costs : Vec<Vec<Vec<Option<usize>>>> = vec![vec![vec![None; num_states]; num_cols]; num_rows];
+    let mut backtrace : HashMap<(usize, usize, usize), (usize, usize, usize)> = HashMap::new();
+    costs[0][0][0] = Some(0);
+    for (row, col) in (0..num_rows).cartesian_product(0..num_cols) {
+        for state in 0..num_states {
+            let cost = match costs[row][col][state] {
+                Some(cost) => cost,
+                None => continue,
+            };
+            let mut relax = |next : (usize, usize, usize), next_cost : usize| {
+                let (next_row, next_col, next_state) = next;
+                let improves = match costs[next_row][next_col][next_state] {
+                    Some(current) => next_cost < current,
+                    None => true,
+                };
+                if improves {
+                    costs[next_row][next_col][next_state] = Some(next_cost);
+                    backtrace.insert(next, (row, col, state));
+                }
+            };
+            if row + 1 < num_rows && col + 1 < num_cols {
+                let step_cost = if graphemes1[row] == graphemes2[col] {0} else {sub_cost};
+                relax((row + 1, col + 1, 0), cost + step_cost);
+            }
+            let insertions = if (1..=max_gap).contains(&state) {state + 1} else {1};
+            if col + 1 < num_cols && insertions <= max_gap {
+                relax((row, col + 1, insertions), cost + 1);
+            }
+            let deletions = if state > max_gap {state - max_gap + 1} else {1};
+            if row + 1 < num_rows && deletions <= max_gap {
+                relax((row + 1, col, max_gap + deletions), cost + 1);
+            }
+        }
+    }
+    let end_state = (0..num_states)
+        .filter_map(|state| costs[num_rows-1][num_cols-1][state].map(|cost| (cost, state)))
+        .min()?.1;
+    let mut path = vec![];
+    let mut node = (num_rows - 1, num_cols - 1, end_state);
+    loop {
+        path.push((node.0, node.1));
+        match backtrace.get(&node) {
+            Some(&previous) => node = previous,
+            None => break,
+        }
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Preference used by `alignment_path_with_secondary` to choose among alignments of equal cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Secondary {
+    /// Place insertions and deletions as close to the start as possible
+    EarliestGaps,
+    /// Place insertions and deletions as close to the end as possible
+    LatestGaps,
+    /// Use the fewest runs of consecutive insertions or consecutive deletions
+    FewestGapRuns,
+    /// Use the longest run of consecutive matches
+    LongestMatchRun,
+}
+
+/// Returns the backtraced path of an optimal alignment like `alignment_path`, choosing among the alignments
+/// of minimal cost the one preferred by `objective`. Remaining ties are broken the same way every time.
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+/// * `objective` - Preference among the optimal alignments
+///
+/// # Example
+/// ```
+/// use nlp::{alignment_path_with_secondary, Secondary};
+/// use nlp::graphemes_struct::Graphemes;
+/// let (graphemes1, graphemes2) = (Graphemes::from("a"), Graphemes::from("aa"));
+/// assert_eq!(alignment_path_with_secondary(&graphemes1, &graphemes2, 1, Secondary::EarliestGaps), vec![(0, 0), (0, 1), (1, 2)]);
+/// assert_eq!(alignment_path_with_secondary(&graphemes1, &graphemes2, 1, Secondary::LatestGaps), vec![(0, 0), (1, 1), (1, 2)]);
+/// ```
+pub fn alignment_path_with_secondary<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, objective : Secondary) -> Vec<Coordinate>
+    where T : Sequence + ?Sized {
+    let num_rows = graphemes1.len() + 1;
+    let num_cols = graphemes2.len() + 1;
+    let step_cost = |row : usize, col : usize| if graphemes1[row] == graphemes2[col] {0} else {sub_cost};
+    let mut costs = vec![vec![0; num_cols]; num_rows];
+    for (row, col) in (0..num_rows).cartesian_product(0..num_cols) {
+        costs[row][col] = match (row, col) {
+            (0, _) => col,
+            (_, 0) => row,
+            _ => (costs[row-1][col] + 1).min(costs[row][col-1] + 1).min(costs[row-1][col-1] + step_cost(row-1, col-1)),
+        };
+    }
+
+    match objective {
+        Secondary::EarliestGaps | Secondary::LatestGaps => {
+            // backtracking from the end, taking gaps first leaves them at the end of the alignment
+            let gaps_first = objective == Secondary::LatestGaps;
+            let mut path = vec![(num_rows - 1, num_cols - 1)];
+            let (mut row, mut col) = (num_rows - 1, num_cols - 1);
+            while (row, col) != (0, 0) {
+                let diagonal = row > 0 && col > 0 && costs[row-1][col-1] + step_cost(row-1, col-1) == costs[row][col];
+                let insertion = col > 0 && costs[row][col-1] + 1 == costs[row][col];
+                let deletion = row > 0 && costs[row-1][col] + 1 == costs[row][col];
+                let previous = if diagonal && !(gaps_first && (insertion || deletion)) {
+                    (row - 1, col - 1)
+                } else if insertion {
+                    (row, col - 1)
+                } else {
+                    debug_assert!(deletion);
+                    (row - 1, col)
+                };
+                row = previous.0;
+                col = previous.1;
+                path.push(previous);
+            }
+            path.reverse();
+            path
+        }
+        Secondary::FewestGapRuns => {
+            // state: the last step was diagonal (0), an insertion (1) or a deletion (2), score: gap runs so far
+            secondary_path(graphemes1, graphemes2, sub_cost, &costs, 3, |state, gap_runs, step| match step {
+                AlignmentStep::Match | AlignmentStep::Substitute => (0, gap_runs),
+                AlignmentStep::Insert => (1, if state == 1 { gap_runs } else { gap_runs + 1 }),
+                AlignmentStep::Delete => (2, if state == 2 { gap_runs } else { gap_runs + 1 }),
+            }, |gap_runs, best| gap_runs < best)
+        }
+        Secondary::LongestMatchRun => {
+            // state: length of the current run of matches, score: longest run of matches so far
+            let num_states = num_rows.min(num_cols);
+            secondary_path(graphemes1, graphemes2, sub_cost, &costs, num_states, |run, longest_run, step| match step {
+                AlignmentStep::Match => (run + 1, longest_run.max(run + 1)),
+                _ => (0, longest_run),
+            }, |longest_run, best| longest_run > best)
+        }
+    }
+}
+
+/// Returns the path through the optimal edges of `costs` whose score is best according to `is_better`.
+/// Every path starts in state 0 with score 0 and `transition` gives the state and score after each step.
+fn secondary_path<T, F, B>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, costs : &[Vec<usize>], num_states : usize,
+                           transition : F, is_better : B) -> Vec<Coordinate>
+    where T : Sequence + ?Sized, F : Fn(usize, usize, AlignmentStep) -> (usize, usize), B : Fn(usize, usize) -> bool {
+    let num_rows = graphemes1.len() + 1;
+    let num_cols = graphemes2.len() + 1;
+    let mut scores : Vec<Vec<Vec<Option<usize>>>> = vec![vec![vec![None; num_states]; num_cols]; num_rows];
+    let mut backtrace : HashMap<(usize, usize, usize), (usize, usize, usize)> = HashMap::new();
+    scores[0][0][0] = Some(0);
+    for (row, col) in (0..num_rows).cartesian_product(0..num_cols) {
+        for state in 0..num_states {
+            let score = match scores[row][col][state] {
+                Some(score) => score,
+                None => continue,
+            };
+            let mut successors = vec![];
+            if row + 1 < num_rows && col + 1 < num_cols {
+                if graphemes1[row] == graphemes2[col] {
+                    successors.push((row + 1, col + 1, AlignmentStep::Match, 0));
+                } else {
+                    successors.push((row + 1, col + 1, AlignmentStep::Substitute, sub_cost));
+                }
+            }
+            if col + 1 < num_cols {
+                successors.push((row, col + 1, AlignmentStep::Insert, 1));
+            }
+            if row + 1 < num_rows {
+                successors.push((row + 1, col, AlignmentStep::Delete, 1));
+            }
+            // only follow steps on an optimal path to the next cell
+            for (next_row, next_col, step, _) in successors.into_iter()
+                .filter(|&(next_row, next_col, _, step_cost)| costs[row][col] + step_cost == costs[next_row][next_col]) {
+                let (next_state, next_score) = transition(state, score, step);
+                let improves = match scores[next_row][next_col][next_state] {
+                    Some(current) => is_better(next_score, current),
+                    None => true,
+                };
+                if improves {
+                    scores[next_row][next_col][next_state] = Some(next_score);
+                    backtrace.insert((next_row, next_col, next_state), (row, col, state));
+                }
+            }
+        }
+    }
+    let end_scores = &scores[num_rows-1][num_cols-1];
+    let end_state = (0..num_states)
+        .filter(|&state| end_scores[state].is_some())
+        .reduce(|best, state| if is_better(end_scores[state].unwrap(), end_scores[best].unwrap()) { state } else { best })
+        .unwrap();
+    let mut path = vec![];
+    let mut node = (num_rows - 1, num_cols - 1, end_state);
+    loop {
+        path.push((node.0, node.1));
+        match backtrace.get(&node) {
+            Some(&previous) => node = previous,
+            None => break,
+        }
+    }
+    path.reverse();
+    path
+}
+
+/// Counts the distinct optimal alignment paths between two words, saturating at `usize::MAX`.
+/// A count of 1 means the alignment is unambiguous, large counts flag unreliable alignments.
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::alignment_ambiguity;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(alignment_ambiguity(&Graphemes::from("北京"), &Graphemes::from("北京"), 1), 1);
+/// // the "a" can be aligned with either "a"
+/// assert_eq!(alignment_ambiguity(&Graphemes::from("a"), &Graphemes::from("aa"), 1), 2);
+/// ```
+pub fn alignment_ambiguity<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> usize
+    where T : Sequence + ?Sized {
+    let num_rows = graphemes1.len() + 1;
+    let num_cols = graphemes2.len() + 1;
+    let mut costs = vec![vec![0; num_cols]; num_rows];
+    let mut counts = vec![vec![1_usize; num_cols]; num_rows];
+    for (row, col) in (0..num_rows).cartesian_product(0..num_cols) {
+        if row == 0 && col == 0 {
+            continue;
+        }
+        let mut predecessors = vec![];
+        if row > 0 {
+            predecessors.push((costs[row-1][col] + 1, counts[row-1][col]));
+        }
+        if col > 0 {
+            predecessors.push((costs[row][col-1] + 1, counts[row][col-1]));
+        }
+        if row > 0 && col > 0 {
+            let step_cost = if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost};
+            predecessors.push((costs[row-1][col-1] + step_cost, counts[row-1][col-1]));
+        }
+        let min_cost = predecessors.iter().map(|&(cost, _)| cost).min().unwrap();
+        costs[row][col] = min_cost;
+        counts[row][col] = predecessors.iter()
+            .filter(|&&(cost, _)| cost == min_cost)
+            .fold(0_usize, |total, &(_, count)| total.saturating_add(count));
+    }
+    counts[num_rows-1][num_cols-1]
+}
+
+/// Returns the posterior probability that `graphemes1[i]` is aligned with `graphemes2[j]` (matched or substituted),
+/// as a `graphemes1.len()` by `graphemes2.len()` matrix. Every alignment path is weighted by `exp(-cost / temperature)`
+/// with insertions, deletions and substitutions costing 1, and the probabilities are summed over all paths with the
+/// forward-backward algorithm. Lower temperatures concentrate the probability on the optimal alignments.
+/// Each row sums to at most 1, the rest being the probability that the grapheme is deleted.
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `temperature` - Positive scale of the costs
+///
+/// # Example
+/// ```
+/// use nlp::alignment_posteriors;
+/// use nlp::graphemes_struct::Graphemes;
+/// let posteriors = alignment_posteriors(&Graphemes::from("ab"), &Graphemes::from("ab"), 0.1);
+/// assert!(posteriors[0][0] > 0.99 && posteriors[1][1] > 0.99);
+/// assert!(posteriors[0][1] < 0.01);
+/// ```
+pub fn alignment_posteriors<T>(graphemes1 : &T, graphemes2 : &T, temperature : f64) -> Vec<Vec<f64>>
+    where T : Sequence + ?Sized {
+    assert!(temperature > 0.0, "temperature must be positive");
+    let num_rows = graphemes1.len() + 1;
+    let num_cols = graphemes2.len() + 1;
+    // log weights of the steps
+    let gap = -1.0 / temperature;
+    let diagonal = |row : usize, col : usize| if graphemes1[row] == graphemes2[col] {0.0} else {gap};
+    let log_add = |a : f64, b : f64| {
+        if a == f64::NEG_INFINITY { return b; }
+        if b == f64::NEG_INFINITY { return a; }
+        a.max(b) + (-(a - b).abs()).exp().ln_1p()
+    };
+    // forward[row][col]: log of the summed weight of the paths from (0, 0) to (row, col)
+    let mut forward = vec![vec![f64::NEG_INFINITY; num_cols]; num_rows];
+    forward[0][0] = 0.0;
+    for (row, col) in (0..num_rows).cartesian_product(0..num_cols) {
+        if row > 0 {
+            forward[row][col] = log_add(forward[row][col], forward[row-1][col] + gap);
+        }
+        if col > 0 {
+            forward[row][col] = log_add(forward[row][col], forward[row][col-1] + gap);
+        }
+        if row > 0 && col > 0 {
+            forward[row][col] = log_add(forward[row][col], forward[row-1][col-1] + diagonal(row-1, col-1));
+        }
+    }
+    // backward[row][col]: log of the summed weight of the paths from (row, col) to the end
+    let mut backward = vec![vec![f64::NEG_INFINITY; num_cols]; num_rows];
+    backward[num_rows-1][num_cols-1] = 0.0;
+    for (row, col) in (0..num_rows).rev().cartesian_product((0..num_cols).rev()) {
+        if row + 1 < num_rows {
+            backward[row][col] = log_add(backward[row][col], backward[row+1][col] + gap);
+        }
+        if col + 1 < num_cols {
+            backward[row][col] = log_add(backward[row][col], backward[row][col+1] + gap);
+        }
+        if row + 1 < num_rows && col + 1 < num_cols {
+            backward[row][col] = log_add(backward[row][col], backward[row+1][col+1] + diagonal(row, col));
+        }
+    }
+    let total = forward[num_rows-1][num_cols-1];
+    (0..num_rows-1).map(|row| (0..num_cols-1).map(|col| {
+        (forward[row][col] + diagonal(row, col) + backward[row+1][col+1] - total).exp()
+    }).collect()).collect()
+}
+
+/// Returns an alignment of two strings as an array of two graphemes
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+/// * `ins_del_char` - &str for indicating insertion/deletion
+///
+/// # Example
+/// ```
+/// use nlp::alignment_strings;
+/// use nlp::graphemes_struct::Graphemes;
+/// let intention = Graphemes::from("intention");
+/// let execution = Graphemes::from("execution");
+/// let strings = alignment_strings(&intention, &execution, 1, " ");
+/// // strings contains
+/// // 0. intention
+/// // 1. execution
+/// ```
+pub fn alignment_strings<T, U>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, ins_del_char : U) -> [T; 2]
+    where T : Default + Sequence<Item = U> + Push<U>, U : Clone {
+    alignment_strings_with(graphemes1, graphemes2, &AlignmentConfig { sub_cost, ..AlignmentConfig::default() }, ins_del_char)
+}
+
+/// Returns an alignment of two strings like `alignment_strings`, with the cost and tie-breaking given by `config`
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `config` - Substitution cost and tie-break preference
+/// * `ins_del_char` - &str for indicating insertion/deletion
+///
+/// # Example
+/// ```
+/// use nlp::{alignment_strings_with, AlignmentConfig};
+/// use nlp::graphemes_struct::Graphemes;
+/// let (graphemes1, graphemes2) = (Graphemes::from("a"), Graphemes::from("b"));
+/// let gaps = AlignmentConfig { sub_cost: 2, prefer_substitution: false };
+/// assert_eq!(alignment_strings_with(&graphemes1, &graphemes2, &gaps, "-"), [Graphemes::from("-a"), Graphemes::from("b-")]);
+/// let diagonal = AlignmentConfig { sub_cost: 2, prefer_substitution: true };
+/// assert_eq!(alignment_strings_with(&graphemes1, &graphemes2, &diagonal, "-"), [Graphemes::from("a"), Graphemes::from("b")]);
+/// ```
+pub fn alignment_strings_with<T, U>(graphemes1 : &T, graphemes2 : &T, config : &AlignmentConfig, ins_del_char : U) -> [T; 2]
+    where T : Default + Sequence<Item = U> + Push<U>, U : Clone {
+    let path = alignment_path_with(graphemes1, graphemes2, config);
+    if path.is_empty() {
+        return [T::default(), T::default()];
+    }
+    let mut align_graphemes1 = T::default();
+    let mut align_graphemes2 = T::default();
+
+    let mut path_iter = path.iter();
+    let mut prev_coord = *path_iter.next().unwrap(); // handled by the if case
+    for &(row, col) in path_iter {
+        if row != 0 && row - 1 == prev_coord.0 && col != 0 && col - 1 == prev_coord.1 {
+            align_graphemes1.push(graphemes1[row-1].clone());
+            align_graphemes2.push(graphemes2[col-1].clone());
+        } else if row == prev_coord.0 && col != 0 && col - 1 == prev_coord.1 {
+            align_graphemes1.push(ins_del_char.clone());
+            align_graphemes2.push(graphemes2[col-1].clone());
+        }
+        else if row != 0 && row - 1 == prev_coord.0 && col == prev_coord.1 {
+            align_graphemes1.push(graphemes1[row-1].clone());
+            align_graphemes2.push(ins_del_char.clone());
+        } else {
+            panic!();
+        }
+        prev_coord = (row, col);
+    }
+    [align_graphemes1, align_graphemes2]
+}
+
+/// Returns a word-level alignment of two sentences as an array of two word columns.
+/// The sentences are split on spaces and each gap is filled with a `***` word.
+/// # Arguments
+/// * `actual_sentence` - Reference sentence
+/// * `predict_sentence` - Predicted sentence
+///
+/// # Example
+/// ```
+/// use nlp::word_alignment_strings;
+/// use nlp::graphemes_struct::Graphemes;
+/// let [actual, predicted] = word_alignment_strings(&Graphemes::from("we can see"), &Graphemes::from("we see it"));
+/// let display = |words : &Vec<Graphemes>| words.iter().map(|word| word.to_string()).collect::<Vec<String>>().join(" ");
+/// // substituting "can" and "see" costs as much as deleting "can" and inserting "it"
+/// assert_eq!(display(&actual), "we can see");
+/// assert_eq!(display(&predicted), "we see it");
+/// ```
+pub fn word_alignment_strings<'a>(actual_sentence : &Graphemes<'a>, predict_sentence : &Graphemes<'a>) -> [Vec<Graphemes<'a>>; 2] {
+    let actual_words = actual_sentence.split(" ");
+    let predict_words = predict_sentence.split(" ");
+    let path = alignment_path(&actual_words, &predict_words, 1);
+    let gap = || Graphemes::from("***");
+    let copy = |word : &Graphemes<'a>| word.slice(0, word.len());
+    let mut aligned_actual = vec![];
+    let mut aligned_predict = vec![];
+    for (step, coords) in alignment_steps(&actual_words, &predict_words, &path).into_iter().zip(path.windows(2)) {
+        let (row, col) = coords[1];
+        match step {
+            AlignmentStep::Match | AlignmentStep::Substitute => {
+                aligned_actual.push(copy(&actual_words[row-1]));
+                aligned_predict.push(copy(&predict_words[col-1]));
+            }
+            AlignmentStep::Insert => {
+                aligned_actual.push(gap());
+                aligned_predict.push(copy(&predict_words[col-1]));
+            }
+            AlignmentStep::Delete => {
+                aligned_actual.push(copy(&actual_words[row-1]));
+                aligned_predict.push(gap());
+            }
+        }
+    }
+    [aligned_actual, aligned_predict]
+}
+
+/// Operation of an aligned column, marking where each run of insertions or deletions opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentOp {
+    Match,
+    Substitute,
+    /// Grapheme only present in `graphemes2`, `gap_open` if the previous column is not an insertion
+    Insert { gap_open : bool },
+    /// Grapheme only present in `graphemes1`, `gap_open` if the previous column is not a deletion
+    Delete { gap_open : bool },
+}
+
+/// Returns the operation of every column of the alignment (as produced by `alignment_strings`),
+/// so that gap openings can be told apart from gap extensions, e.g. for affine gap costs
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::{alignment_ops, AlignmentOp};
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(alignment_ops(&Graphemes::from("ac"), &Graphemes::from("abbc"), 1), vec![
+///     AlignmentOp::Match,
+///     AlignmentOp::Insert { gap_open: true },
+///     AlignmentOp::Insert { gap_open: false },
+///     AlignmentOp::Match,
+/// ]);
+/// ```
+pub fn alignment_ops<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<AlignmentOp>
+    where T : Sequence + ?Sized {
+    let path = alignment_path(graphemes1, graphemes2, sub_cost);
+    let steps = alignment_steps(graphemes1, graphemes2, &path);
+    steps.iter().enumerate().map(|(i, &step)| {
+        let gap_open = i == 0 || steps[i-1] != step;
+        match step {
+            AlignmentStep::Match => AlignmentOp::Match,
+            AlignmentStep::Substitute => AlignmentOp::Substitute,
+            AlignmentStep::Insert => AlignmentOp::Insert { gap_open },
+            AlignmentStep::Delete => AlignmentOp::Delete { gap_open },
+        }
+    }).collect()
+}
+
+/// Returns, for every column of the alignment (as produced by `alignment_strings`), the index of the grapheme
+/// of `graphemes1` and of `graphemes2` in that column, or `None` on the side of a gap
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::alignment_index_pairs;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(alignment_index_pairs(&Graphemes::from("北京"), &Graphemes::from("北京市"), 1),
+///            vec![(Some(0), Some(0)), (Some(1), Some(1)), (None, Some(2))]);
+/// ```
+pub fn alignment_index_pairs<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<(Option<usize>, Option<usize>)>
+    where T : Sequence + ?Sized {
+    let path = alignment_path(graphemes1, graphemes2, sub_cost);
+    alignment_steps(graphemes1, graphemes2, &path).into_iter().zip(path.windows(2)).map(|(step, coords)| {
+        let (row, col) = coords[1];
+        match step {
+            AlignmentStep::Match | AlignmentStep::Substitute => (Some(row - 1), Some(col - 1)),
+            AlignmentStep::Insert => (None, Some(col - 1)),
+            AlignmentStep::Delete => (Some(row - 1), None),
+        }
+    }).collect()
+}
+
+/// Edit turning `graphemes1` into `graphemes2`, with the indices of the graphemes involved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditOp {
+    /// Grapheme `.0` of `graphemes1` equals grapheme `.1` of `graphemes2`
+    Match(usize, usize),
+    /// Grapheme `.0` of `graphemes1` is replaced by grapheme `.1` of `graphemes2`
+    Substitute(usize, usize),
+    /// Grapheme `.0` of `graphemes2` is inserted
+    Insert(usize),
+    /// Grapheme `.0` of `graphemes1` is deleted
+    Delete(usize),
+}
+
+/// Returns the edit of every column of the alignment (as produced by `alignment_strings`)
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::{edit_operations, EditOp};
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(edit_operations(&Graphemes::from("北京"), &Graphemes::from("南京市"), 1),
+///            vec![EditOp::Substitute(0, 0), EditOp::Match(1, 1), EditOp::Insert(2)]);
+/// ```
+pub fn edit_operations<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<EditOp>
+    where T : Sequence + ?Sized {
+    let path = alignment_path(graphemes1, graphemes2, sub_cost);
+    alignment_steps(graphemes1, graphemes2, &path).into_iter().zip(path.windows(2)).map(|(step, coords)| {
+        let (row, col) = coords[1];
+        match step {
+            AlignmentStep::Match => EditOp::Match(row - 1, col - 1),
+            AlignmentStep::Substitute => EditOp::Substitute(row - 1, col - 1),
+            AlignmentStep::Insert => EditOp::Insert(col - 1),
+            AlignmentStep::Delete => EditOp::Delete(row - 1),
+        }
+    }).collect()
+}
+
+/// Returns the alignment (as produced by `alignment_strings`) as pairs of aligned graphemes,
+/// with `None` on the side of a gap instead of a padding character
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::aligned_pairs;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(aligned_pairs(&Graphemes::from("北京"), &Graphemes::from("北京市"), 1),
+///            vec![(Some("北"), Some("北")), (Some("京"), Some("京")), (None, Some("市"))]);
+/// ```
+pub fn aligned_pairs<'a>(graphemes1 : &Graphemes<'a>, graphemes2 : &Graphemes<'a>, sub_cost : usize) -> Vec<(Option<&'a str>, Option<&'a str>)> {
+    alignment_index_pairs(graphemes1, graphemes2, sub_cost).into_iter()
+        .map(|(index1, index2)| (index1.map(|index| graphemes1[index]), index2.map(|index| graphemes2[index])))
+        .collect()
+}
+
+/// Returns the cost of every column of the alignment (as produced by `alignment_strings`):
+/// 0 for a match, `sub_cost` for a substitution and 1 for an insertion or deletion.
+/// The costs add up to the levenshtein distance, and can be drawn as a heatmap of where two words diverge.
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::cost_contributions;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(cost_contributions(&Graphemes::from("北京烤鸭"), &Graphemes::from("南京鸭"), 1), vec![1, 0, 1, 0]);
+/// ```
+pub fn cost_contributions<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<usize>
+    where T : Sequence + ?Sized {
+    let path = alignment_path(graphemes1, graphemes2, sub_cost);
+    alignment_steps(graphemes1, graphemes2, &path).into_iter().map(|step| match step {
+        AlignmentStep::Match => 0,
+        AlignmentStep::Substitute => sub_cost,
+        AlignmentStep::Insert | AlignmentStep::Delete => 1,
+    }).collect()
+}
+
+/// Returns the column indices of the aligned output (as produced by `alignment_strings`)
+/// where the two sequences disagree, i.e. substitutions, insertions and deletions
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::difference_positions;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(difference_positions(&Graphemes::from("北京烤鸭"), &Graphemes::from("南京烤鸭"), 1), vec![0]);
+/// assert!(difference_positions(&Graphemes::from("same"), &Graphemes::from("same"), 1).is_empty());
+/// ```
+pub fn difference_positions<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<usize>
+    where T : Sequence + ?Sized {
+    let path = alignment_path(graphemes1, graphemes2, sub_cost);
+    alignment_steps(graphemes1, graphemes2, &path).into_iter()
+        .enumerate()
+        .filter(|&(_, step)| step != AlignmentStep::Match)
+        .map(|(column, _)| column)
+        .collect()
+}
+
+/// Shown by `side_by_side` on the side of a gap. A shade block rather than `-`, so that a gap
+/// is not mistaken for a hyphen of the text.
+pub const SIDE_BY_SIDE_GAP : &str = "░";
+
+/// Returns the alignment of two words (as produced by `alignment_strings`) as pairs of left and right lines
+/// for a side-by-side view in a terminal. Gaps are shown as `SIDE_BY_SIDE_GAP`, and every aligned pair of graphemes
+/// is padded with spaces to the display width of the wider one so both sides stay in step.
+/// Lines are wrapped like `Graphemes::wrap`, where a column counts as whitespace or a line break only if it is one
+/// on both sides, and padded to exactly `width` columns. A column wider than `width` gets a line of its own.
+///
+/// # Arguments
+/// * `graphemes1` - Graphemes shown on the left
+/// * `graphemes2` - Graphemes shown on the right
+/// * `sub_cost` - Cost of substituting a character with another
+/// * `width` - Number of terminal columns of each side
+///
+/// # Example
+/// ```
+/// use nlp::side_by_side;
+/// use nlp::graphemes_struct::Graphemes;
+/// let lines = side_by_side(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京a鸭"), 1, 5);
+/// assert_eq!(lines, vec![(String::from("北京 "), String::from("北京 ")), (String::from("烤鸭 "), String::from("a 鸭 "))]);
+/// ```
+pub fn side_by_side(graphemes1 : &Graphemes, graphemes2 : &Graphemes, sub_cost : usize, width : usize) -> Vec<(String, String)> {
+    let columns : Vec<(&str, &str)> = aligned_pairs(graphemes1, graphemes2, sub_cost).into_iter()
+        .map(|(grapheme1, grapheme2)| (grapheme1.unwrap_or(SIDE_BY_SIDE_GAP), grapheme2.unwrap_or(SIDE_BY_SIDE_GAP)))
+        .collect();
+    // a column is a space or a line break only if it is one on both sides
+    let cells : Vec<WrapCell> = columns.iter().map(|&(grapheme1, grapheme2)| {
+        match (WrapCell::of(grapheme1), WrapCell::of(grapheme2)) {
+            (WrapCell::Newline, WrapCell::Newline) => WrapCell::Newline,
+            (WrapCell::Space(width1), WrapCell::Space(width2)) => WrapCell::Space(width1.max(width2)),
+            _ => WrapCell::Text(grapheme_width(grapheme1).max(grapheme_width(grapheme2))),
+        }
+    }).collect();
+    let pad = |line : &mut String, from : usize, to : usize| line.extend((from..to).map(|_| ' '));
+    wrap_ranges(&cells, width).into_iter().map(|(start, end)| {
+        let (mut left, mut right) = (String::new(), String::new());
+        for (&(grapheme1, grapheme2), cell) in columns[start..end].iter().zip(cells[start..end].iter()) {
+            left.push_str(grapheme1);
+            pad(&mut left, grapheme_width(grapheme1), cell.columns());
+            right.push_str(grapheme2);
+            pad(&mut right, grapheme_width(grapheme2), cell.columns());
+        }
+        let line_width = cells[start..end].iter().map(WrapCell::columns).sum();
+        pad(&mut left, line_width, width);
+        pad(&mut right, line_width, width);
+        (left, right)
+    }).collect()
+}
+
+/// Largest number of cells of the cost cube `align3` accepts
+const MAX_ALIGN3_CELLS : usize = 1 << 24;
+
+/// Aligns three sequences at once, e.g. the base, mine and theirs versions of a three-way merge, returning one
+/// column per aligned position with `None` on the side of a gap. The columns minimize the sum of the pairwise costs:
+/// each pair of graphemes costs `sub_cost` when different, a grapheme against a gap costs 1 and two gaps cost nothing.
+/// The dynamic program fills a cube of `(base.len() + 1) * (mine.len() + 1) * (theirs.len() + 1)` cells,
+/// so it takes O(n³) time and memory and is meant for short inputs. Panics beyond 2^24 cells.
+/// # Arguments
+/// * `base` - Common ancestor
+/// * `mine` - First version
+/// * `theirs` - Second version
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::align3;
+/// use nlp::graphemes_struct::Graphemes;
+/// let columns = align3(&Graphemes::from("ab"), &Graphemes::from("xab"), &Graphemes::from("a"), 1);
+/// assert_eq!(columns, vec![(None, Some("x"), None), (Some("a"), Some("a"), Some("a")), (Some("b"), Some("b"), None)]);
+/// ```
+pub fn align3<'a>(base : &Graphemes<'a>, mine : &Graphemes<'a>, theirs : &Graphemes<'a>, sub_cost : usize)
+    -> Vec<(Option<&'a str>, Option<&'a str>, Option<&'a str>)> {
+    let lens = [base.len(), mine.len(), theirs.len()];
+    assert!(lens.iter().try_fold(1_usize, |cells, &len| cells.checked_mul(len + 1)).is_some_and(|cells| cells <= MAX_ALIGN3_CELLS),
+            "align3 is limited to {} cells", MAX_ALIGN3_CELLS);
+    let pair_cost = |grapheme1 : Option<&str>, grapheme2 : Option<&str>| match (grapheme1, grapheme2) {
+        (Some(grapheme1), Some(grapheme2)) => if grapheme1 == grapheme2 {0} else {sub_cost},
+        (None, None) => 0,
+        _ => 1,
+    };
+    // the column ending at (i, j, k) reached from the cell `step` before it, where each bit of `step` advances one sequence
+    let column = |(i, j, k) : (usize, usize, usize), step : usize| (
+        if step & 1 != 0 { Some(base[i-1]) } else { None },
+        if step & 2 != 0 { Some(mine[j-1]) } else { None },
+        if step & 4 != 0 { Some(theirs[k-1]) } else { None },
+    );
+    let previous = |(i, j, k) : (usize, usize, usize), step : usize| -> Option<(usize, usize, usize)> {
+        Some((i.checked_sub(step & 1)?, j.checked_sub((step >> 1) & 1)?, k.checked_sub((step >> 2) & 1)?))
+    };
+    let step_cost = |cell : (usize, usize, usize), step : usize| {
+        let (a, b, c) = column(cell, step);
+        pair_cost(a, b) + pair_cost(a, c) + pair_cost(b, c)
+    };
+    // columns advancing all three sequences are preferred on ties
+    const STEPS : [usize; 7] = [7, 3, 5, 6, 1, 2, 4];
+
+    let mut costs = vec![vec![vec![0; lens[2] + 1]; lens[1] + 1]; lens[0] + 1];
+    for i in 0..=lens[0] {
+        for j in 0..=lens[1] {
+            for k in 0..=lens[2] {
+                costs[i][j][k] = STEPS.iter()
+                    .filter_map(|&step| previous((i, j, k), step).map(|(pi, pj, pk)| costs[pi][pj][pk] + step_cost((i, j, k), step)))
+                    .min()
+                    .unwrap_or(0);
+            }
+        }
+    }
+
+    let mut columns = vec![];
+    let mut cell = (lens[0], lens[1], lens[2]);
+    while cell != (0, 0, 0) {
+        let (step, before) = STEPS.iter()
+            .filter_map(|&step| previous(cell, step).map(|before| (step, before)))
+            .find(|&(step, (pi, pj, pk))| costs[pi][pj][pk] + step_cost(cell, step) == costs[cell.0][cell.1][cell.2])
+            .unwrap();
+        columns.push(column(cell, step));
+        cell = before;
+    }
+    columns.reverse();
+    columns
+}
+
+/// Counts the substitutions of the alignment (as produced by `alignment_strings`) whose two graphemes
+/// differ only in case, as compared by `Graphemes::eq_ignore_case`. The remaining differences
+/// are substantive and number `difference_positions(..).len()` minus this count.
+/// # Arguments
+/// * `graphemes1` - Graphemes to compare with `graphemes2`
+/// * `graphemes2` - Graphemes to compare with `graphemes1`
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::case_only_differences;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(case_only_differences(&Graphemes::from("Hello World"), &Graphemes::from("hello wurld"), 1), 2);
+/// ```
+pub fn case_only_differences(graphemes1 : &Graphemes, graphemes2 : &Graphemes, sub_cost : usize) -> usize {
+    aligned_pairs(graphemes1, graphemes2, sub_cost).into_iter()
+        .filter(|&(grapheme1, grapheme2)| match (grapheme1, grapheme2) {
+            (Some(grapheme1), Some(grapheme2)) => grapheme1 != grapheme2
+                && Graphemes::from(grapheme1).eq_ignore_case(&Graphemes::from(grapheme2)),
+            _ => false
+        })
+        .count()
+}
+
+/// Returns the alignment of two words as HTML. Runs of inserted graphemes are wrapped in `<ins>`,
+/// runs of deleted graphemes in `<del>` and runs of substituted graphemes in `<span class="sub">`
+/// whose `title` holds the graphemes of `graphemes1` they replace. All content is HTML-escaped.
+/// # Arguments
+/// * `graphemes1` - Original graphemes
+/// * `graphemes2` - Modified graphemes
+/// * `sub_cost` - Cost of substituting a character with another
+///
+/// # Example
+/// ```
+/// use nlp::alignment_html;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(alignment_html(&Graphemes::from("我爱北京"), &Graphemes::from("我爱南京!"), 1),
+///            "我爱<span class=\"sub\" title=\"北\">南</span>京<ins>!</ins>");
+/// ```
+pub fn alignment_html(graphemes1 : &Graphemes, graphemes2 : &Graphemes, sub_cost : usize) -> String {
+    let path = alignment_path(graphemes1, graphemes2, sub_cost);
+    let steps = alignment_steps(graphemes1, graphemes2, &path);
+    let mut html = String::new();
+    let mut run_start = 0;
+    while run_start < steps.len() {
+        let step = steps[run_start];
+        let run_end = run_start + steps[run_start..].iter().take_while(|&&run_step| run_step == step).count();
+        let mut original = String::new();
+        let mut modified = String::new();
+        for &(row, col) in &path[run_start+1..=run_end] {
+            if step != AlignmentStep::Insert {
+                original.push_str(graphemes1[row-1]);
+            }
+            if step != AlignmentStep::Delete {
+                modified.push_str(graphemes2[col-1]);
+            }
+        }
+        match step {
+            AlignmentStep::Match => html.push_str(&escape_html(&modified)),
+            AlignmentStep::Insert => html.push_str(&format!("<ins>{}</ins>", escape_html(&modified))),
+            AlignmentStep::Delete => html.push_str(&format!("<del>{}</del>", escape_html(&original))),
+            AlignmentStep::Substitute => html.push_str(&format!("<span class=\"sub\" title=\"{}\">{}</span>",
+                                                                escape_html(&original), escape_html(&modified))),
+        }
+        run_start = run_end;
+    }
+    html
+}
+
+fn escape_html(text : &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A token of a segmented sentence together with the graphemes of the unsegmented sentence it was aligned to
+#[derive(Debug, PartialEq, Eq)]
+pub struct Correspondence<'a> {
+    /// Index of the pair in the corpus
+    pub pair_index : usize,
+    /// Span `(start, end)` of graphemes in the unsegmented sentence
+    pub source_span : Coordinate,
+    /// Token of the segmented sentence
+    pub target : Graphemes<'a>,
+}
+
+/// Iterates over the token correspondences of a corpus of (unsegmented, segmented) sentence pairs.
+/// Each pair is aligned grapheme by grapheme and every space separated token of the segmented sentence
+/// is matched with the span of unsegmented graphemes aligned to it, so spans may differ from the token
+/// when the pair is not an exact segmentation. The spans of a pair cover the whole unsegmented sentence:
+/// graphemes aligned to the spaces between two tokens go to the first one.
+/// # Arguments
+/// * `pairs` - Unsegmented sentences with their segmentation
+///
+/// # Example
+/// ```
+/// use nlp::align_corpus;
+/// use nlp::graphemes_struct::Graphemes;
+/// let pairs = vec![(Graphemes::from("北京烤鸭"), Graphemes::from("北京 烤鸭"))];
+/// let spans : Vec<(usize, usize)> = align_corpus(&pairs).map(|correspondence| correspondence.source_span).collect();
+/// assert_eq!(spans, vec![(0, 2), (2, 4)]);
+/// ```
+pub fn align_corpus<'p, 'a>(pairs : &'p [(Graphemes<'a>, Graphemes<'a>)]) -> impl Iterator<Item = Correspondence<'a>> + 'p {
+    pairs.iter().enumerate().flat_map(|(pair_index, (source, target))| {
+        let path = alignment_path(source, target, 1);
+        // number of source graphemes aligned once the first `col` target graphemes are
+        let mut rows_at_col = vec![0; target.len() + 1];
+        for &(row, col) in path.iter() {
+            rows_at_col[col] = row;
+        }
+        let mut token_spans = vec![];
+        let mut token_start = 0;
+        for token_end in 0..=target.len() {
+            if token_end < target.len() && target[token_end] != " " {
+                continue;
+            }
+            if token_start < token_end {
+                token_spans.push((token_start, token_end));
+            }
+            token_start = token_end + 1;
+        }
+        // each token extends up to where the next token starts so the spans cover the whole source
+        let mut correspondences = vec![];
+        let mut source_start = 0;
+        for (i, &(token_start, token_end)) in token_spans.iter().enumerate() {
+            let source_end = token_spans.get(i + 1).map_or(source.len(), |&(next_start, _)| rows_at_col[next_start]);
+            correspondences.push(Correspondence {
+                pair_index,
+                source_span: (source_start, source_end),
+                target: target.slice(token_start, token_end),
+            });
+            source_start = source_end;
+        }
+        correspondences
+    })
+}
+
+/// Builds a dictionary of graphemes from a collection of strings
+/// # Arguments
+/// * `words` - Words to segment into graphemes and insert into the dictionary
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, max_match};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["他", "特别", "喜欢", "北京烤鸭"]);
+/// let sentence = max_match(&Graphemes::from("他特别喜欢北京烤鸭"), &dictionary);
+/// assert_eq!(&sentence, &Graphemes::from("他 特别 喜欢 北京烤鸭"));
+///
+/// let owned_words = vec![String::from("hello"), String::from("world")];
+/// assert!(dictionary_from(&owned_words).contains(&Graphemes::from("world")));
+/// ```
+pub fn dictionary_from<'a, I, S>(words : I) -> HashSet<Graphemes<'a>>
+    where I : IntoIterator<Item = &'a S>, S : AsRef<str> + ?Sized + 'a {
+    words.into_iter().map(|word| Graphemes::from(word.as_ref())).collect()
+}
+
+/// Returns a random subset of a dictionary containing `fraction` of its words (rounded to the nearest integer).
+/// The words are drawn with a random number generator seeded with `seed` from the sorted dictionary,
+/// so the same dictionary and seed always give the same subset.
+/// # Arguments
+/// * `dictionary` - Words to sample from
+/// * `fraction` - Proportion of the words to keep, between 0 and 1
+/// * `seed` - Seed making the sampling reproducible
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, random_subset};
+/// let dictionary = dictionary_from(&["他", "特别", "喜欢", "北京烤鸭"]);
+/// let subset = random_subset(&dictionary, 0.5, 42);
+/// assert_eq!(subset.len(), 2);
+/// assert!(subset.is_subset(&dictionary));
+/// assert_eq!(subset, random_subset(&dictionary, 0.5, 42));
+/// ```
+#[cfg(feature = "rand")]
+pub fn random_subset<'a>(dictionary : &HashSet<Graphemes<'a>>, fraction : f64, seed : u64) -> HashSet<Graphemes<'a>> {
+    use rand::{Rng, SeedableRng, rngs::SmallRng};
+    assert!((0.0..=1.0).contains(&fraction), "fraction must be between 0 and 1");
+    // hash set iteration order changes between runs
+    let mut words : Vec<&Graphemes<'a>> = dictionary.iter().collect();
+    words.sort_by(|word1, word2| word1[..].cmp(&word2[..]));
+    let subset_len = (fraction * words.len() as f64).round() as usize;
+    let mut rng = SmallRng::seed_from_u64(seed);
+    // partial Fisher-Yates shuffle of the first `subset_len` words
+    for i in 0..subset_len {
+        let j = rng.gen_range(i..words.len());
+        words.swap(i, j);
+    }
+    words[..subset_len].iter().map(|word| word.slice(0, word.len())).collect()
+}
+
+/// Segments a sentence with space using the max match algorithm
+/// # Arguments
+/// * `sentence` - Sentence composed of words unseperated to be segmented
+/// * `dictionary` - HashSet containing words for matching possible words in the sentence for segmentation
+///
+/// # Example
+/// ```
+/// use nlp::max_match;
+/// use nlp::graphemes_struct::Graphemes;
+/// use std::collections::HashSet;
+/// let mut dictionary : HashSet<Graphemes> = HashSet::new();
+///        dictionary.insert(Graphemes::from("他"));
+///        dictionary.insert(Graphemes::from("特别"));
+///        dictionary.insert(Graphemes::from("喜欢"));
+///        dictionary.insert(Graphemes::from("北京烤鸭"));
+/// let sentence = max_match(&Graphemes::from("他特别喜欢北京烤鸭"), &dictionary);
+/// assert_eq!(&sentence, &Graphemes::from("他 特别 喜欢 北京烤鸭"));
+/// ```
+pub fn max_match<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>) -> Graphemes<'a> {
+    let mut segmented = Graphemes::new();
+    for (i, word) in max_match_tokens(sentence, dictionary).into_iter().enumerate() {
+        if i > 0 {
+            segmented.push(" ");
+        }
+        segmented.append(word);
+    }
+    segmented
+}
+
+/// Segments a sentence with the max match algorithm like `max_match`, but returns the words
+/// instead of joining them with spaces, so words containing a space are kept whole
+/// # Arguments
+/// * `sentence` - Sentence composed of words unseperated to be segmented
+/// * `dictionary` - HashSet containing words for matching possible words in the sentence for segmentation
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, max_match_tokens};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["new york", "is", "big"]);
+/// assert_eq!(max_match_tokens(&Graphemes::from("new yorkisbig"), &dictionary),
+///            vec![Graphemes::from("new york"), Graphemes::from("is"), Graphemes::from("big")]);
+/// ```
+pub fn max_match_tokens<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>) -> Vec<Graphemes<'a>> {
+    max_match_spans(sentence, dictionary).into_iter().map(|(start, end)| sentence.slice(start, end)).collect()
+}
+
+/// Segments a sentence with space using the backward max match algorithm: the longest dictionary word
+/// ending at the end of the sentence is taken first, then the longest one ending before it, and so on.
+/// Like `max_match`, a grapheme starting no dictionary word is taken on its own.
+/// # Arguments
+/// * `sentence` - Sentence composed of words unseperated to be segmented
+/// * `dictionary` - HashSet containing words for matching possible words in the sentence for segmentation
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, max_match, max_match_backward};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["研究", "研究生", "生命", "起源"]);
+/// let sentence = Graphemes::from("研究生命起源");
+/// assert_eq!(max_match_backward(&sentence, &dictionary), Graphemes::from("研究 生命 起源"));
+/// assert_eq!(max_match(&sentence, &dictionary), Graphemes::from("研究生 命 起源"));
+/// ```
+pub fn max_match_backward<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>) -> Graphemes<'a> {
+    let mut spans = vec![];
+    let mut end = sentence.len();
+    while end > 0 {
+        let start = (0..end-1)
+            .find(|&start| dictionary.contains(&sentence.slice(start, end)))
+            .unwrap_or(end - 1);
+        spans.push((start, end));
+        end = start;
+    }
+    let mut segmented = Graphemes::new();
+    for (i, &(start, end)) in spans.iter().rev().enumerate() {
+        if i > 0 {
+            segmented.push(" ");
+        }
+        segmented.append(sentence.slice(start, end));
+    }
+    segmented
+}
+
+/// Returns the number of words of a single grapheme in a sentence segmented with space,
+/// e.g. graphemes left over by `max_match` because they start no dictionary word
+/// # Arguments
+/// * `segmented` - Words separated by single spaces
+///
+/// # Example
+/// ```
+/// use nlp::single_grapheme_words;
+/// use nlp::graphemes_struct::Graphemes;
+/// assert_eq!(single_grapheme_words(&Graphemes::from("研究生 命 起源")), 1);
+/// assert_eq!(single_grapheme_words(&Graphemes::from("")), 0);
+/// ```
+pub fn single_grapheme_words(segmented : &Graphemes) -> usize {
+    segmented.split(" ").iter().filter(|word| word.len() == 1).count()
+}
+
+/// Segments a sentence with space using both `max_match` and `max_match_backward`, keeping the segmentation
+/// with fewer single grapheme words, then with fewer words. Backward matching wins remaining ties,
+/// as it is more often right for Chinese.
+/// # Arguments
+/// * `sentence` - Sentence composed of words unseperated to be segmented
+/// * `dictionary` - HashSet containing words for matching possible words in the sentence for segmentation
 ///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, max_match_bidirectional};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["研究", "研究生", "生命", "起源"]);
+/// assert_eq!(max_match_bidirectional(&Graphemes::from("研究生命起源"), &dictionary), Graphemes::from("研究 生命 起源"));
+/// ```
+pub fn max_match_bidirectional<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>) -> Graphemes<'a> {
+    let forward = max_match(sentence, dictionary);
+    let backward = max_match_backward(sentence, dictionary);
+    let score = |segmented : &Graphemes| (single_grapheme_words(segmented), segmented.split(" ").len());
+    if score(&forward) < score(&backward) {
+        forward
+    } else {
+        backward
+    }
+}
+
+/// Probability given to a grapheme which is not in the dictionary when segmenting with `viterbi_segment`
+const OOV_GRAPHEME_PROBABILITY : f64 = 1e-10;
+
+/// Segments a sentence into the words whose product of unigram probabilities is highest, found with the Viterbi algorithm
+/// over log probabilities. Graphemes not in the dictionary are words of their own with a tiny probability,
+/// so every sentence has a segmentation. Between equally likely segmentations, the longer last word is kept.
 /// # Arguments
-/// * `graphemes1` - Graphemes to compare with `graphemes2`
-/// * `graphemes2` - Graphemes to compare with `graphemes1`
-/// * `sub_cost` - Cost of substituting a character with another
+/// * `sentence` - Sentence composed of words unseperated to be segmented
+/// * `dictionary` - Probability of every word, words with a probability of 0 or less are ignored
 ///
 /// # Example
 /// ```
-/// use nlp::alignment_path;
+/// use nlp::{max_match, viterbi_segment};
 /// use nlp::graphemes_struct::Graphemes;
-///
-/// alignment_path(&Graphemes::from("dog"), &Graphemes::from("woof"), 1);
-/// // returns [(0, 0), (1, 1), (2, 2), (3, 3), (3, 4)]
+/// use std::collections::HashMap;
+/// let dictionary : HashMap<Graphemes, f64> = [("研究", 0.1), ("研究生", 0.01), ("生命", 0.1), ("命", 0.001), ("起源", 0.1)]
+///     .iter().map(|&(word, probability)| (Graphemes::from(word), probability)).collect();
+/// assert_eq!(viterbi_segment(&Graphemes::from("研究生命起源"), &dictionary), Graphemes::from("研究 生命 起源").split(" "));
 /// ```
-pub fn alignment_path<'a, T, U>(graphemes1 : &T, graphemes2: &T, sub_cost : usize) -> Vec<Coordinate>
-    where T : Len + Index<usize, Output = U>, U : PartialEq + 'a {
-    let mat = alignment_matrix(graphemes1, graphemes2, sub_cost);
-    let mut path = backtrace_alignment_matrix((graphemes1.len(), graphemes2.len()), mat);
-    path.reverse();
-    path
+pub fn viterbi_segment<'a>(sentence : &Graphemes<'a>, dictionary : &HashMap<Graphemes, f64>) -> Vec<Graphemes<'a>> {
+    let max_word_len = dictionary.keys().map(|word| word.len()).max().unwrap_or(0).max(1);
+    let log_probability = |word : &Graphemes| match dictionary.get(word) {
+        Some(&probability) if probability > 0.0 => Some(probability.ln()),
+        _ if word.len() == 1 => Some(OOV_GRAPHEME_PROBABILITY.ln()),
+        _ => None,
+    };
+    // best[end]: highest log probability of a segmentation of the first `end` graphemes, and where its last word starts
+    let mut best : Vec<(f64, usize)> = vec![(f64::NEG_INFINITY, 0); sentence.len() + 1];
+    best[0].0 = 0.0;
+    for end in 1..=sentence.len() {
+        for start in end.saturating_sub(max_word_len)..end {
+            if let Some(word_log_probability) = log_probability(&sentence.slice(start, end)) {
+                let total = best[start].0 + word_log_probability;
+                if total > best[end].0 {
+                    best[end] = (total, start);
+                }
+            }
+        }
+    }
+    let mut words = vec![];
+    let mut end = sentence.len();
+    while end > 0 {
+        let start = best[end].1;
+        words.push(sentence.slice(start, end));
+        end = start;
+    }
+    words.reverse();
+    words
 }
 
-/// Returns an alignment of two strings as an array of two graphemes
+/// Segments a sentence mixing CJK and other scripts with space. Runs of Han, Hiragana, Katakana and Hangul
+/// graphemes are segmented with `max_match`, while runs of any other graphemes are kept as whole words,
+/// so "coding" is not split into letters. Whitespace separates runs and is replaced by a single space.
 /// # Arguments
-/// * `graphemes1` - Graphemes to compare with `graphemes2`
-/// * `graphemes2` - Graphemes to compare with `graphemes1`
-/// * `sub_cost` - Cost of substituting a character with another
-/// * `ins_del_char` - &str for indicating insertion/deletion
+/// * `sentence` - Sentence to be segmented
+/// * `dictionary` - HashSet containing words for matching possible words in the CJK runs
 ///
 /// # Example
 /// ```
-/// use nlp::alignment_strings;
+/// use nlp::{dictionary_from, segment_mixed, max_match};
 /// use nlp::graphemes_struct::Graphemes;
-/// let intention = Graphemes::from("intention");
-/// let execution = Graphemes::from("execution");
-/// let strings = alignment_strings(&intention, &execution, 1, " ");
-/// // strings contains
-/// // 0. inten tion
-/// // 1. ex ecution
+/// let dictionary = dictionary_from(&["他", "喜欢"]);
+/// assert_eq!(segment_mixed(&Graphemes::from("他喜欢coding"), &dictionary), Graphemes::from("他 喜欢 coding"));
+/// assert_eq!(max_match(&Graphemes::from("他喜欢coding"), &dictionary), Graphemes::from("他 喜欢 c o d i n g"));
 /// ```
-pub fn alignment_strings<'a, T, U>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, ins_del_char : U) -> [T; 2]
-    where T : 'a + Default + Len + Push<U> + Index<usize, Output = U>, U : PartialEq + Clone + 'a{
-    let path = alignment_path(graphemes1, graphemes2, sub_cost);
-    if path.is_empty() {
-        return [T::default(), T::default()];
+pub fn segment_mixed<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>) -> Graphemes<'a> {
+    use crate::graphemes_struct::Script;
+    let is_cjk = |script : Script| [Script::Han, Script::Hiragana, Script::Katakana, Script::Hangul].contains(&script);
+    let is_space = |grapheme : &str| grapheme.chars().all(char::is_whitespace);
+    let scripts = sentence.scripts();
+    let mut segmented = Graphemes::new();
+    let mut run_start = 0;
+    while run_start < sentence.len() {
+        if is_space(sentence[run_start]) {
+            run_start += 1;
+            continue;
+        }
+        let cjk = is_cjk(scripts[run_start]);
+        let run_len = (run_start..sentence.len())
+            .take_while(|&i| !is_space(sentence[i]) && is_cjk(scripts[i]) == cjk)
+            .count();
+        let run = sentence.slice(run_start, run_start + run_len);
+        if !segmented.is_empty() {
+            segmented.push(" ");
+        }
+        segmented.append(if cjk { max_match(&run, dictionary) } else { run });
+        run_start += run_len;
     }
-    let mut align_graphemes1 = T::default();
-    let mut align_graphemes2 = T::default();
+    segmented
+}
 
-    let mut path_iter = path.iter();
-    let mut prev_coord = *path_iter.next().unwrap(); // handled by the if case
-    for &(row, col) in path_iter {
-        if row != 0 && row - 1 == prev_coord.0 && col != 0 && col - 1 == prev_coord.1 {
-            align_graphemes1.push(graphemes1[row-1].clone());
-            align_graphemes2.push(graphemes2[col-1].clone());
-        } else if row == prev_coord.0 && col != 0 && col - 1 == prev_coord.1 {
-            align_graphemes1.push(ins_del_char.clone());
-            align_graphemes2.push(graphemes2[col-1].clone());
+/// Segments every sentence with `max_match`, calling `progress` with (number of sentences segmented, total)
+/// after each one, e.g. to drive a progress bar
+/// # Arguments
+/// * `sentences` - Sentences composed of words unseperated to be segmented
+/// * `dictionary` - HashSet containing words for matching possible words in the sentences
+/// * `progress` - Called once per sentence with the counts so far
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, segment_all};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["他", "特别", "喜欢", "北京烤鸭"]);
+/// let sentences = vec![Graphemes::from("他喜欢北京烤鸭"), Graphemes::from("他特别喜欢")];
+/// let segmented = segment_all(&sentences, &dictionary, |done, total| eprintln!("{}/{}", done, total));
+/// assert_eq!(segmented, vec![Graphemes::from("他 喜欢 北京烤鸭"), Graphemes::from("他 特别 喜欢")]);
+/// ```
+pub fn segment_all<'a, F>(sentences : &[Graphemes<'a>], dictionary : &HashSet<Graphemes>, mut progress : F) -> Vec<Graphemes<'a>>
+    where F : FnMut(usize, usize) {
+    sentences.iter().enumerate().map(|(i, sentence)| {
+        let segmented = max_match(sentence, dictionary);
+        progress(i + 1, sentences.len());
+        segmented
+    }).collect()
+}
+
+/// How well a dictionary covers a corpus, as computed by `dictionary_coverage`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    /// Fraction of the graphemes of the corpus inside dictionary words
+    pub grapheme_coverage : f64,
+    /// Fraction of the segmented words which are not in the dictionary
+    pub oov_rate : f64,
+    /// Distinct spans of consecutive out of vocabulary graphemes, in order of first appearance
+    pub oov_tokens : Vec<String>,
+}
+
+/// Segments every sentence of a corpus with `max_match` and reports how much of it the dictionary covers.
+/// Graphemes not covered by any dictionary word come out of `max_match` as single grapheme words,
+/// which are out of vocabulary. Whitespace graphemes are not counted.
+/// # Arguments
+/// * `dictionary` - HashSet containing words for matching possible words in the sentences
+/// * `corpus` - Sentences composed of words unseperated
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, dictionary_coverage};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["他", "喜欢"]);
+/// let coverage = dictionary_coverage(&dictionary, &[Graphemes::from("他喜欢北京")]);
+/// assert_eq!(coverage.grapheme_coverage, 0.6);
+/// assert_eq!(coverage.oov_rate, 0.5);
+/// assert_eq!(coverage.oov_tokens, vec!["北京"]);
+/// ```
+pub fn dictionary_coverage(dictionary : &HashSet<Graphemes>, corpus : &[Graphemes]) -> Coverage {
+    let (mut covered_graphemes, mut total_graphemes, mut oov_words, mut total_words) = (0, 0, 0, 0);
+    let mut oov_tokens : Vec<String> = vec![];
+    let mut end_span = |oov_span : &mut String| {
+        if !oov_span.is_empty() && !oov_tokens.contains(oov_span) {
+            oov_tokens.push(oov_span.clone());
         }
-        else if row != 0 && row - 1 == prev_coord.0 && col == prev_coord.1 {
-            align_graphemes1.push(graphemes1[row-1].clone());
-            align_graphemes2.push(ins_del_char.clone());
-        } else {
-            panic!();
+        oov_span.clear();
+    };
+    for sentence in corpus.iter() {
+        let mut oov_span = String::new();
+        for word in max_match(sentence, dictionary).split(" ") {
+            if word.iter().all(|grapheme| grapheme.chars().all(char::is_whitespace)) {
+                // spaces of the sentence become empty words, which also end an unknown span
+                end_span(&mut oov_span);
+                continue;
+            }
+            total_words += 1;
+            total_graphemes += word.len();
+            if dictionary.contains(&word) {
+                covered_graphemes += word.len();
+                end_span(&mut oov_span);
+            } else {
+                oov_words += 1;
+                oov_span.push_str(&word.to_string());
+            }
         }
-        prev_coord = (row, col);
+        end_span(&mut oov_span);
+    }
+    let ratio = |part : usize, total : usize, empty : f64| if total == 0 { empty } else { part as f64 / total as f64 };
+    Coverage {
+        grapheme_coverage: ratio(covered_graphemes, total_graphemes, 1.0),
+        oov_rate: ratio(oov_words, total_words, 0.0),
+        oov_tokens,
     }
-    [align_graphemes1, align_graphemes2]
 }
 
-/// Segments a sentence with space using the max match algorithm
+/// How `max_match_ignoring` treats ignorable graphemes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnorableMode {
+    /// Remove ignorable graphemes from the output
+    Strip,
+    /// Keep ignorable graphemes in the output, inside the word they appeared in
+    PassThrough,
+}
+
+/// Segments a sentence with space using the max match algorithm while skipping ignorable graphemes
+/// (e.g. zero-width spaces, byte order marks or control characters), so they cannot break up dictionary words
 /// # Arguments
 /// * `sentence` - Sentence composed of words unseperated to be segmented
 /// * `dictionary` - HashSet containing words for matching possible words in the sentence for segmentation
+/// * `ignorable` - Graphemes skipped during matching
+/// * `mode` - Whether ignorable graphemes are stripped or passed through to the output
 ///
 /// # Example
 /// ```
-/// use nlp::max_match;
+/// use nlp::{dictionary_from, max_match, max_match_ignoring, IgnorableMode};
 /// use nlp::graphemes_struct::Graphemes;
 /// use std::collections::HashSet;
-/// let mut dictionary : HashSet<Graphemes> = HashSet::new();
-///        dictionary.insert(Graphemes::from("他"));
-///        dictionary.insert(Graphemes::from("特别"));
-///        dictionary.insert(Graphemes::from("喜欢"));
-///        dictionary.insert(Graphemes::from("北京烤鸭"));
-/// let sentence = max_match(&Graphemes::from("他特别喜欢北京烤鸭"), &dictionary);
-/// assert_eq!(&sentence, &Graphemes::from("他 特别 喜欢 北京烤鸭"));
+/// let dictionary = dictionary_from(&["喜欢", "北京烤鸭"]);
+/// let ignorable : HashSet<&str> = ["\u{200B}", "\u{FEFF}"].iter().cloned().collect();
+/// let sentence = Graphemes::from("喜欢北\u{200B}京烤鸭");
+/// assert_eq!(max_match(&sentence, &dictionary), Graphemes::from("喜欢 北 \u{200B} 京 烤 鸭"));
+/// assert_eq!(max_match_ignoring(&sentence, &dictionary, &ignorable, IgnorableMode::Strip),
+///            Graphemes::from("喜欢 北京烤鸭"));
+/// assert_eq!(max_match_ignoring(&sentence, &dictionary, &ignorable, IgnorableMode::PassThrough),
+///            Graphemes::from("喜欢 北\u{200B}京烤鸭"));
 /// ```
-pub fn max_match<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>) -> Graphemes<'a> {
-    if sentence.is_empty() {
-        return Graphemes::from("");
-    }
-    for i in (1..sentence.len()+1).rev() {
-        let mut first_word = sentence.slice(0,i);
-        let remainder = sentence.slice(i, sentence.len());
-        if dictionary.contains(&first_word) {
-            if !remainder.is_empty() {
-                first_word.push(" ");
+pub fn max_match_ignoring<'a>(sentence : &Graphemes<'a>, dictionary : &HashSet<Graphemes>, ignorable : &HashSet<&str>, mode : IgnorableMode) -> Graphemes<'a> {
+    // indices in `sentence` of the graphemes taking part in matching
+    let kept_indices : Vec<usize> = (0..sentence.len()).filter(|&i| !ignorable.contains(sentence[i])).collect();
+    let kept : Graphemes<'a> = kept_indices.iter().fold(Graphemes::new(), |mut kept, &i| {
+        kept.push(sentence[i]);
+        kept
+    });
+    let word_starts : Vec<usize> = max_match_spans(&kept, dictionary).iter().map(|&(start, _)| start).collect();
+    if word_starts.is_empty() && mode == IgnorableMode::PassThrough {
+        return sentence.slice(0, sentence.len());
+    }
+    let mut segmented = Graphemes::new();
+    for (word_index, &start) in word_starts.iter().enumerate() {
+        if word_index > 0 {
+            segmented.push(" ");
+        }
+        match mode {
+            IgnorableMode::Strip => {
+                let end = word_starts.get(word_index + 1).cloned().unwrap_or_else(|| kept.len());
+                segmented.append(kept.slice(start, end));
             }
-            first_word.append(max_match(&remainder, dictionary));
-            return first_word;
+            IgnorableMode::PassThrough => {
+                // leading ignorables belong to the first word, the rest trail the word before them
+                let original_start = if word_index == 0 {0} else {kept_indices[start]};
+                let original_end = word_starts.get(word_index + 1).map_or(sentence.len(), |&next| kept_indices[next]);
+                segmented.append(sentence.slice(original_start, original_end));
+            }
+        }
+    }
+    segmented
+}
+
+/// Returns the `(start, end)` grapheme spans of the words found by the max match algorithm
+fn max_match_spans(sentence : &Graphemes, dictionary : &HashSet<Graphemes>) -> Vec<Coordinate> {
+    let mut spans = vec![];
+    let mut start = 0;
+    while start < sentence.len() {
+        let end = (start+2..sentence.len()+1).rev()
+            .find(|&end| dictionary.contains(&sentence.slice(start, end)))
+            .unwrap_or(start + 1);
+        spans.push((start, end));
+        start = end;
+    }
+    spans
+}
+
+/// Merges runs of consecutive single-grapheme tokens which are not in the dictionary into one token,
+/// e.g. the out-of-vocabulary letters left behind by `max_match`
+/// # Arguments
+/// * `tokens` - Segmented words
+/// * `dictionary` - HashSet containing the known words
+///
+/// # Example
+/// ```
+/// use nlp::{dictionary_from, max_match, merge_unknown_runs};
+/// use nlp::graphemes_struct::Graphemes;
+/// let dictionary = dictionary_from(&["他", "喜欢"]);
+/// let sentence = max_match(&Graphemes::from("他喜欢rust"), &dictionary);
+/// assert_eq!(&sentence, &Graphemes::from("他 喜欢 r u s t"));
+/// let tokens = merge_unknown_runs(&sentence.split(" "), &dictionary);
+/// assert_eq!(tokens, vec![Graphemes::from("他"), Graphemes::from("喜欢"), Graphemes::from("rust")]);
+/// ```
+pub fn merge_unknown_runs<'a>(tokens : &[Graphemes<'a>], dictionary : &HashSet<Graphemes>) -> Vec<Graphemes<'a>> {
+    let mut merged_tokens : Vec<Graphemes<'a>> = vec![];
+    let mut in_unknown_run = false;
+    for token in tokens {
+        let is_unknown = token.len() == 1 && !dictionary.contains(token);
+        let token = token.slice(0, token.len());
+        match merged_tokens.last_mut() {
+            Some(last_token) if is_unknown && in_unknown_run => last_token.append(token),
+            _ => merged_tokens.push(token),
+        }
+        in_unknown_run = is_unknown;
+    }
+    merged_tokens
+}
+
+/// Kind of step taken between two consecutive coordinates of an alignment path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlignmentStep {
+    Match,
+    Substitute,
+    Insert,
+    Delete,
+}
+
+fn alignment_steps<T>(graphemes1 : &T, graphemes2 : &T, path : &[Coordinate]) -> Vec<AlignmentStep>
+    where T : Sequence + ?Sized {
+    path.windows(2).map(|coords| {
+        let (prev_row, prev_col) = coords[0];
+        let (row, col) = coords[1];
+        if row == prev_row + 1 && col == prev_col + 1 {
+            if graphemes1[row-1] == graphemes2[col-1] { AlignmentStep::Match } else { AlignmentStep::Substitute }
+        } else if row == prev_row && col == prev_col + 1 {
+            AlignmentStep::Insert
+        } else if row == prev_row + 1 && col == prev_col {
+            AlignmentStep::Delete
+        } else {
+            panic!();
         }
+    }).collect()
+}
+
+/// View over `sequence[start..end]` so generic sequences can be aligned piecewise
+struct SubSequence<'s, T : ?Sized> {
+    sequence : &'s T,
+    start : usize,
+    end : usize,
+}
+
+impl<'s, T : ?Sized> Empty for SubSequence<'s, T> {
+    fn is_empty(&self) -> bool {
+        self.start == self.end
     }
-    let mut first_word = sentence.slice(0,1);
-    let remainder = sentence.slice(1, sentence.len());
+}
 
-    if !remainder.is_empty() {
-        first_word.push(" ");
+impl<'s, T : ?Sized> Len for SubSequence<'s, T> {
+    fn len(&self) -> usize {
+        self.end - self.start
     }
-    first_word.append(max_match(&remainder, dictionary));
-    return first_word;
 }
 
+impl<'s, T : Sequence + ?Sized> Index<usize> for SubSequence<'s, T> {
+    type Output = T::Item;
+
+    fn index(&self, index : usize) -> &T::Item {
+        &self.sequence[self.start + index]
+    }
+}
 
 fn backtrace_alignment_matrix<'a>(start_coord : Coordinate, backtrace : HashMap<Coordinate, Coordinate>) -> Vec<Coordinate>{
     let mut path  = vec![];
@@ -137,38 +1580,28 @@ fn backtrace_alignment_matrix<'a>(start_coord : Coordinate, backtrace : HashMap<
     path
 }
 
-fn alignment_matrix<'a, T, U>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> HashMap<Coordinate, Coordinate>
-    where T : Len + Index<usize, Output = U>, U : PartialEq + 'a {
+fn alignment_matrix<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, prefer_substitution : bool) -> HashMap<Coordinate, Coordinate>
+    where T : Sequence + ?Sized {
     let num_rows = graphemes1.len() + 1;
     let num_cols = graphemes2.len() + 1;
     let mut backtrace : HashMap<Coordinate, Coordinate> = HashMap::new();
-    let mut recurrence_matrix : Vec<Vec<usize>> = vec![vec![0; num_cols]; num_rows];
+    let recurrence_matrix = cost_matrix(graphemes1, graphemes2, sub_cost);
     // graphemes1 → row
     // graphemes2 → column
     for row in 1..num_rows {
-        recurrence_matrix[row][0] = row;
         backtrace.insert((row, 0), (row-1, 0));
     }
     for col in 1..num_cols {
-        recurrence_matrix[0][col] = col;
         backtrace.insert((0, col), (0, col-1));
     }
 
     for (row, col) in (1..num_rows).cartesian_product(1..num_cols) {
-        let mut min_distance = recurrence_matrix[row][col-1] + 1;
-        let mut min_coordinate = (row, col-1);
-        let current_del_cost = recurrence_matrix[row-1][col] + 1;
-        if current_del_cost < min_distance {
-            min_distance = current_del_cost;
-            min_coordinate = (row-1, col);
-        }
-        let current_sub_cost = recurrence_matrix[row-1][col-1] + if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost};
-        if current_sub_cost < min_distance {
-            min_distance = current_sub_cost;
-            min_coordinate = (row-1, col-1);
-        }
-
-        recurrence_matrix[row][col] = min_distance;
+        let ins = (recurrence_matrix[row][col-1] + 1, (row, col-1));
+        let del = (recurrence_matrix[row-1][col] + 1, (row-1, col));
+        let sub = (recurrence_matrix[row-1][col-1] + if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost}, (row-1, col-1));
+        // on ties the first of the candidates wins
+        let candidates = if prefer_substitution {[sub, del, ins]} else {[del, ins, sub]};
+        let (_, min_coordinate) = *candidates.iter().min_by_key(|(cost, _)| *cost).unwrap();
         backtrace.insert((row, col), min_coordinate);
     }
     backtrace
@@ -232,25 +1665,133 @@ mod test_cases {
             &Graphemes::from("aaaaaaaaaaaaaaaaa"), &Graphemes::from(""), 1, " "), 17);
     }
 
+    fn first_difference<T : Sequence + ?Sized>(sequence1 : &T, sequence2 : &T) -> Option<usize> {
+        (0..sequence1.len().min(sequence2.len())).find(|&i| sequence1[i] != sequence2[i])
+    }
+
+    #[test]
+    fn sequence_trait_test() {
+        let graphemes1 = Graphemes::from("北京烤鸭");
+        let graphemes2 = Graphemes::from("北京烤肉");
+        assert_eq!(first_difference(&graphemes1, &graphemes2), Some(3));
+        assert_eq!(metrics::levenshtein_distance(&graphemes1, &graphemes2, 1), 1);
+
+        let words1 = vec![Graphemes::from("北京"), Graphemes::from("烤鸭")];
+        let words2 = vec![Graphemes::from("北京"), Graphemes::from("烤鸭"), Graphemes::from("好")];
+        assert_eq!(first_difference(&words1, &words2), None);
+        assert_eq!(metrics::levenshtein_distance(&words1, &words2, 1), 1);
+        assert_eq!(alignment_path(&words1, &words2, 1), vec![(0, 0), (1, 1), (2, 2), (2, 3)]);
+
+        let strs = vec!["a", "b"];
+        assert_eq!(first_difference(&strs, &vec!["a", "c"]), Some(1));
+    }
+
+    #[test]
+    fn alignment_with_anchors_test() {
+        let graphemes1 = Graphemes::from("intention");
+        let graphemes2 = Graphemes::from("execution");
+        let anchors = [(2, 4), (7, 7)];
+        let path = alignment_with_anchors(&graphemes1, &graphemes2, 1, &anchors);
+        for &(row, col) in anchors.iter() {
+            let position = path.iter().position(|&coord| coord == (row, col)).unwrap();
+            assert_eq!(path[position + 1], (row + 1, col + 1));
+        }
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(9, 9)));
+        for window in path.windows(2) {
+            assert!(window[1].0 - window[0].0 <= 1 && window[1].1 - window[0].1 <= 1);
+            assert!(window[1] != window[0]);
+        }
+        assert_eq!(alignment_with_anchors(&graphemes1, &graphemes2, 1, &[]), alignment_path(&graphemes1, &graphemes2, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "anchors must be strictly increasing and within both sequences")]
+    fn alignment_with_unordered_anchors_test() {
+        alignment_with_anchors(&Graphemes::from("intention"), &Graphemes::from("execution"), 1, &[(7, 7), (2, 4)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "anchors must be strictly increasing and within both sequences")]
+    fn alignment_with_out_of_range_anchors_test() {
+        alignment_with_anchors(&Graphemes::from("abc"), &Graphemes::from("ab"), 1, &[(1, 2)]);
+    }
+
     fn chinese_dictionary() -> HashSet<Graphemes<'static>> {
-        let mut dictionary : HashSet<Graphemes> = HashSet::new();
-        dictionary.insert(Graphemes::from("他"));
-        dictionary.insert(Graphemes::from("特别"));
-        dictionary.insert(Graphemes::from("喜欢"));
-        dictionary.insert(Graphemes::from("北京烤鸭"));
-        dictionary
+        dictionary_from(&["他", "特别", "喜欢", "北京烤鸭"])
     }
 
     fn english_dictionary() -> HashSet<Graphemes<'static>> {
-        let mut dictionary : HashSet<Graphemes> = HashSet::new();
-        dictionary.insert(Graphemes::from("we"));
-        dictionary.insert(Graphemes::from("canon"));
-        dictionary.insert(Graphemes::from("see"));
-        dictionary.insert(Graphemes::from("ash"));
-        dictionary.insert(Graphemes::from("ort"));
-        dictionary.insert(Graphemes::from("distance"));
-        dictionary.insert(Graphemes::from("ahead"));
-        dictionary
+        dictionary_from(&["we", "canon", "see", "ash", "ort", "distance", "ahead"])
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_subset_test() {
+        let dictionary = english_dictionary();
+        let subset = random_subset(&dictionary, 0.5, 7);
+        assert_eq!(subset.len(), (dictionary.len() as f64 * 0.5).round() as usize);
+        assert!(subset.is_subset(&dictionary));
+        // rebuilding the dictionary changes its iteration order but not the subset
+        assert_eq!(random_subset(&english_dictionary(), 0.5, 7), subset);
+        assert_ne!(random_subset(&dictionary, 0.5, 8), subset);
+        assert!(random_subset(&dictionary, 0.0, 7).is_empty());
+        assert_eq!(random_subset(&dictionary, 1.0, 7), dictionary);
+    }
+
+    #[test]
+    fn segment_mixed_test() {
+        let dictionary = chinese_dictionary();
+        assert_eq!(segment_mixed(&Graphemes::from("他喜欢coding"), &dictionary), Graphemes::from("他 喜欢 coding"));
+        assert_eq!(segment_mixed(&Graphemes::from("他特别喜欢Rust和北京烤鸭"), &dictionary),
+                   Graphemes::from("他 特别 喜欢 Rust 和 北京烤鸭"));
+        // whitespace between runs collapses to a single space and Latin words stay separate
+        assert_eq!(segment_mixed(&Graphemes::from("  hello   world 他特别 "), &dictionary), Graphemes::from("hello world 他 特别"));
+        // digits and punctuation are not CJK
+        assert_eq!(segment_mixed(&Graphemes::from("他喜欢2019年"), &dictionary), Graphemes::from("他 喜欢 2019 年"));
+        assert_eq!(segment_mixed(&Graphemes::from("他特别喜欢北京烤鸭"), &dictionary), max_match(&Graphemes::from("他特别喜欢北京烤鸭"), &dictionary));
+        assert!(segment_mixed(&Graphemes::from(" "), &dictionary).is_empty());
+    }
+
+    #[test]
+    fn dictionary_coverage_test() {
+        let dictionary = chinese_dictionary();
+        let corpus = [Graphemes::from("他特别喜欢北京烤鸭"), Graphemes::from("他喜欢上海菜"), Graphemes::from("她 特别 喜欢上海")];
+        let coverage = dictionary_coverage(&dictionary, &corpus);
+        // 他 特别 喜欢 北京烤鸭 | 他 喜欢 上 海 菜 | 她 特别 喜欢 上 海
+        assert_eq!(coverage.grapheme_coverage, 16.0 / 22.0);
+        assert_eq!(coverage.oov_rate, 6.0 / 14.0);
+        assert_eq!(coverage.oov_tokens, vec!["上海菜", "她", "上海"]);
+
+        let full = dictionary_coverage(&dictionary, &corpus[..1]);
+        assert_eq!(full, Coverage { grapheme_coverage: 1.0, oov_rate: 0.0, oov_tokens: vec![] });
+        assert_eq!(dictionary_coverage(&HashSet::new(), &[Graphemes::from("ab c")]).oov_tokens, vec!["ab", "c"]);
+        assert_eq!(dictionary_coverage(&dictionary, &[]), Coverage { grapheme_coverage: 1.0, oov_rate: 0.0, oov_tokens: vec![] });
+    }
+
+    #[test]
+    fn segment_all_test() {
+        let dictionary = chinese_dictionary();
+        let sentences = vec![Graphemes::from("他特别喜欢北京烤鸭"), Graphemes::from(""), Graphemes::from("北京烤鸭")];
+        let mut calls = vec![];
+        let segmented = segment_all(&sentences, &dictionary, |done, total| calls.push((done, total)));
+        assert_eq!(segmented, vec![Graphemes::from("他 特别 喜欢 北京烤鸭"), Graphemes::from(""), Graphemes::from("北京烤鸭")]);
+        assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+        let mut calls = 0;
+        assert!(segment_all(&[], &dictionary, |_, _| calls += 1).is_empty());
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn dictionary_from_test() {
+        let dictionary = dictionary_from(&["他", "特别", "", "他"]);
+        assert_eq!(dictionary.len(), 3);
+        assert!(dictionary.contains(&Graphemes::from("特别")));
+        assert!(dictionary.contains(&Graphemes::from("")));
+        let owned_words = vec![String::from("a̐é"), String::from("ö")];
+        let owned_dictionary = dictionary_from(&owned_words);
+        assert!(owned_dictionary.contains(&Graphemes::from("a̐é")));
+        assert!(!owned_dictionary.contains(&Graphemes::from("o")));
     }
 
     #[test]
@@ -267,4 +1808,478 @@ mod test_cases {
         let example_sentence = max_match(&Graphemes::from("wecanonlyseeashortdistanceahead"), &english_dictionary);
         assert_eq!(&example_sentence, &Graphemes::from("we canon l y see ash ort distance ahead"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn max_match_tokens_test() {
+        let chinese_dictionary = chinese_dictionary();
+        let words = max_match_tokens(&Graphemes::from("他特别喜欢北京烤鸭"), &chinese_dictionary);
+        assert_eq!(words.len(), 4);
+        assert_eq!(words, Graphemes::from("他 特别 喜欢 北京烤鸭").split(" "));
+        assert!(max_match_tokens(&Graphemes::from(""), &chinese_dictionary).is_empty());
+        assert_eq!(max_match_tokens(&Graphemes::from("他们"), &chinese_dictionary), vec![Graphemes::from("他"), Graphemes::from("们")]);
+        // a space inside a dictionary word does not split it
+        let dictionary = dictionary_from(&["北京 烤鸭", "喜欢"]);
+        assert_eq!(max_match_tokens(&Graphemes::from("喜欢北京 烤鸭"), &dictionary), vec![Graphemes::from("喜欢"), Graphemes::from("北京 烤鸭")]);
+    }
+
+    #[test]
+    fn max_match_backward_test() {
+        let chinese_dictionary = chinese_dictionary();
+        assert!(max_match_backward(&Graphemes::from(""), &chinese_dictionary).is_empty());
+        let sentence = max_match_backward(&Graphemes::from("他特别喜欢北京烤鸭"), &chinese_dictionary);
+        assert_eq!(&sentence, &Graphemes::from("他 特别 喜欢 北京烤鸭"));
+        assert_eq!(max_match_backward(&Graphemes::from("english"), &chinese_dictionary), Graphemes::from("e n g l i s h"));
+
+        // forward matching leaves "子" on its own, backward matching leaves "结"
+        let dictionary = dictionary_from(&["结合", "合成", "成分", "分子", "时"]);
+        let sentence = Graphemes::from("结合成分子时");
+        assert_eq!(max_match(&sentence, &dictionary), Graphemes::from("结合 成分 子 时"));
+        assert_eq!(max_match_backward(&sentence, &dictionary), Graphemes::from("结 合成 分子 时"));
+    }
+
+    #[test]
+    fn max_match_bidirectional_test() {
+        let dictionary = dictionary_from(&["研究", "研究生", "生命", "起源", "北京", "烤鸭", "京烤鸭"]);
+        // backward matching leaves no single grapheme
+        let sentence = Graphemes::from("研究生命起源");
+        assert_eq!(single_grapheme_words(&max_match(&sentence, &dictionary)), 1);
+        assert_eq!(single_grapheme_words(&max_match_backward(&sentence, &dictionary)), 0);
+        assert_eq!(max_match_bidirectional(&sentence, &dictionary), Graphemes::from("研究 生命 起源"));
+        // forward matching leaves no single grapheme
+        let sentence = Graphemes::from("北京烤鸭");
+        assert_eq!(max_match_backward(&sentence, &dictionary), Graphemes::from("北 京烤鸭"));
+        assert_eq!(max_match_bidirectional(&sentence, &dictionary), Graphemes::from("北京 烤鸭"));
+        // as many single graphemes, but fewer words
+        let dictionary = dictionary_from(&["abc", "abcde", "ef"]);
+        let sentence = Graphemes::from("abcdef");
+        assert_eq!(max_match(&sentence, &dictionary), Graphemes::from("abcde f"));
+        assert_eq!(max_match_backward(&sentence, &dictionary), Graphemes::from("abc d ef"));
+        assert_eq!(max_match_bidirectional(&sentence, &dictionary), Graphemes::from("abcde f"));
+        // backward matching wins a full tie
+        let dictionary = dictionary_from(&["结合", "合成", "成分", "分子", "时"]);
+        assert_eq!(max_match_bidirectional(&Graphemes::from("结合成分子时"), &dictionary), Graphemes::from("结 合成 分子 时"));
+        assert!(max_match_bidirectional(&Graphemes::from(""), &dictionary).is_empty());
+    }
+
+    #[test]
+    fn single_grapheme_words_test() {
+        assert_eq!(single_grapheme_words(&Graphemes::from("e n g l i s h")), 7);
+        assert_eq!(single_grapheme_words(&Graphemes::from("他 特别 喜欢 北京烤鸭")), 1);
+        assert_eq!(single_grapheme_words(&Graphemes::from("特别")), 0);
+        assert_eq!(single_grapheme_words(&Graphemes::from("")), 0);
+    }
+
+    #[test]
+    fn viterbi_segment_test() {
+        let probabilities = |words : &[(&'static str, f64)]| -> HashMap<Graphemes<'static>, f64> {
+            words.iter().map(|&(word, probability)| (Graphemes::from(word), probability)).collect()
+        };
+        let words = |sentence : &'static str| Graphemes::from(sentence).split(" ");
+        // max match takes "研究生" and is left with "命"
+        let dictionary = probabilities(&[("研究", 0.1), ("研究生", 0.01), ("生命", 0.1), ("命", 0.001), ("起源", 0.1)]);
+        let sentence = Graphemes::from("研究生命起源");
+        let set : HashSet<Graphemes> = dictionary.keys().map(|word| word.slice(0, word.len())).collect();
+        assert_eq!(max_match(&sentence, &set), Graphemes::from("研究生 命 起源"));
+        assert_eq!(viterbi_segment(&sentence, &dictionary), words("研究 生命 起源"));
+        // the same words with other frequencies favor the long match
+        let dictionary = probabilities(&[("研究", 0.001), ("研究生", 0.1), ("生命", 0.001), ("命", 0.1), ("起源", 0.1)]);
+        assert_eq!(viterbi_segment(&sentence, &dictionary), words("研究生 命 起源"));
+        // unknown graphemes are words of their own
+        assert_eq!(viterbi_segment(&Graphemes::from("他们喜欢rust"), &probabilities(&[("喜欢", 0.5), ("他", 0.1), ("他们", 0.0)])),
+                   words("他 们 喜欢 r u s t"));
+        assert!(viterbi_segment(&Graphemes::from(""), &dictionary).is_empty());
+        assert_eq!(viterbi_segment(&Graphemes::from("abc"), &HashMap::new()), words("a b c"));
+    }
+
+    #[test]
+    fn merge_unknown_runs_test() {
+        let chinese_dictionary = chinese_dictionary();
+        let english = max_match(&Graphemes::from("english"), &chinese_dictionary);
+        assert_eq!(merge_unknown_runs(&english.split(" "), &chinese_dictionary), vec![Graphemes::from("english")]);
+
+        let mixed = max_match(&Graphemes::from("他特别喜欢rust和北京烤鸭"), &chinese_dictionary);
+        assert_eq!(merge_unknown_runs(&mixed.split(" "), &chinese_dictionary),
+                   Graphemes::from("他 特别 喜欢 rust和 北京烤鸭").split(" "));
+        // a known single grapheme word ends the unknown run
+        let known = max_match(&Graphemes::from("ab他cd"), &chinese_dictionary);
+        assert_eq!(merge_unknown_runs(&known.split(" "), &chinese_dictionary), Graphemes::from("ab 他 cd").split(" "));
+        assert!(merge_unknown_runs(&[], &chinese_dictionary).is_empty());
+    }
+
+    #[test]
+    fn word_alignment_strings_test() {
+        let english_dictionary = english_dictionary();
+        let predicted_sentence = max_match(&Graphemes::from("wecanonlyseeashortdistanceahead"), &english_dictionary);
+        let actual_sentence = Graphemes::from("we can only see a short distance ahead");
+        let [actual, predicted] = word_alignment_strings(&actual_sentence, &predicted_sentence);
+        let join = |words : &Vec<Graphemes>| words.iter().map(|word| word.to_string()).collect::<Vec<String>>().join(" ");
+        assert_eq!(join(&actual), "we *** can only see a short distance ahead");
+        assert_eq!(join(&predicted), "we canon l y see ash ort distance ahead");
+    }
+
+    #[test]
+    fn alignment_html_test() {
+        assert_eq!(alignment_html(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1),
+                   "<span class=\"sub\" title=\"k\">s</span>itt<span class=\"sub\" title=\"e\">i</span>n<ins>g</ins>");
+        assert_eq!(alignment_html(&Graphemes::from("a<b>&c"), &Graphemes::from("a&c"), 1), "a<del>&lt;b&gt;</del>&amp;c");
+        assert_eq!(alignment_html(&Graphemes::from("\"x\""), &Graphemes::from("'x'"), 1),
+                   "<span class=\"sub\" title=\"&quot;\">&#39;</span>x<span class=\"sub\" title=\"&quot;\">&#39;</span>");
+        assert_eq!(alignment_html(&Graphemes::from(""), &Graphemes::from(""), 1), "");
+    }
+
+    #[test]
+    fn align_corpus_test() {
+        let pairs = vec![
+            (Graphemes::from("他特别喜欢北京烤鸭"), Graphemes::from("他 特别 喜欢 北京烤鸭")),
+            // the extra "e" of "seee" is aligned to a space
+            (Graphemes::from("wecanseeeashortdistance"), Graphemes::from("we can see a short distance")),
+            (Graphemes::from(""), Graphemes::from("")),
+        ];
+        let correspondences : Vec<Correspondence> = align_corpus(&pairs).collect();
+        let summary : Vec<(usize, (usize, usize), String)> = correspondences.iter()
+            .map(|correspondence| (correspondence.pair_index, correspondence.source_span, correspondence.target.to_string()))
+            .collect();
+        assert_eq!(summary, vec![
+            (0, (0, 1), String::from("他")),
+            (0, (1, 3), String::from("特别")),
+            (0, (3, 5), String::from("喜欢")),
+            (0, (5, 9), String::from("北京烤鸭")),
+            (1, (0, 2), String::from("we")),
+            (1, (2, 5), String::from("can")),
+            (1, (5, 9), String::from("see")),
+            (1, (9, 10), String::from("a")),
+            (1, (10, 15), String::from("short")),
+            (1, (15, 23), String::from("distance")),
+        ]);
+        assert_eq!(pairs[1].0.slice(5, 9), Graphemes::from("seee"));
+        // the leading "x" is not aligned to any token and goes to the first one
+        let pairs = vec![(Graphemes::from("xab"), Graphemes::from("a b"))];
+        let spans : Vec<(usize, usize)> = align_corpus(&pairs).map(|correspondence| correspondence.source_span).collect();
+        assert_eq!(spans, vec![(0, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn alignment_posteriors_test() {
+        let kitten = Graphemes::from("kitten");
+        let sitting = Graphemes::from("sitting");
+        for &temperature in [0.2, 1.0, 5.0].iter() {
+            let posteriors = alignment_posteriors(&kitten, &sitting, temperature);
+            assert_eq!(posteriors.len(), kitten.len());
+            for row in posteriors.iter() {
+                assert_eq!(row.len(), sitting.len());
+                assert!(row.iter().all(|&posterior| (0.0..=1.0 + 1e-9).contains(&posterior)));
+                assert!(row.iter().sum::<f64>() <= 1.0 + 1e-9);
+            }
+        }
+        // the diagonal steps of the optimal path get the highest posterior of their row
+        let posteriors = alignment_posteriors(&kitten, &sitting, 0.2);
+        let path = alignment_path(&kitten, &sitting, 1);
+        for coords in path.windows(2) {
+            let ((prev_row, prev_col), (row, col)) = (coords[0], coords[1]);
+            if row == prev_row + 1 && col == prev_col + 1 {
+                let best = posteriors[row-1].iter().cloned().fold(0.0, f64::max);
+                assert_eq!(posteriors[row-1][col-1], best);
+                assert!(best > 0.9);
+            }
+        }
+        assert!(alignment_posteriors(&Graphemes::from(""), &sitting, 1.0).is_empty());
+        assert!(alignment_posteriors(&kitten, &Graphemes::from(""), 1.0).iter().all(|row| row.is_empty()));
+    }
+
+    #[test]
+    fn alignment_max_gap_test() {
+        let path_cost = |graphemes1 : &Graphemes, graphemes2 : &Graphemes, path : &[(usize, usize)]| {
+            alignment_steps(graphemes1, graphemes2, path).iter().filter(|&&step| step != AlignmentStep::Match).count()
+        };
+        let longest_gap = |graphemes1 : &Graphemes, graphemes2 : &Graphemes, path : &[(usize, usize)]| {
+            alignment_steps(graphemes1, graphemes2, path).into_iter()
+                .group_by(|&step| step).into_iter()
+                .filter(|(step, _)| *step == AlignmentStep::Insert || *step == AlignmentStep::Delete)
+                .map(|(_, run)| run.count())
+                .max().unwrap_or(0)
+        };
+        let (abcdef, af) = (Graphemes::from("abcdef"), Graphemes::from("af"));
+        // four deletions need at least three runs of one, but the two graphemes of "af" only separate two
+        assert_eq!(alignment_max_gap(&abcdef, &af, 1, 1), None);
+        assert_eq!(alignment_max_gap(&af, &abcdef, 1, 1), None);
+        // deleting "bcd" and "f" costs an extra substitution of "e" for "f"
+        let path = alignment_max_gap(&abcdef, &af, 1, 3).unwrap();
+        assert_eq!(path_cost(&abcdef, &af, &path), 5);
+        assert_eq!(longest_gap(&abcdef, &af, &path), 3);
+        let path = alignment_max_gap(&abcdef, &af, 1, 4).unwrap();
+        assert_eq!((path[0], path[path.len()-1]), ((0, 0), (6, 2)));
+        assert_eq!(path_cost(&abcdef, &af, &path), 4);
+        // "cd" has to be inserted with a substitution in between
+        let (ab, cdab) = (Graphemes::from("ab"), Graphemes::from("cdab"));
+        let path = alignment_max_gap(&ab, &cdab, 1, 1).unwrap();
+        assert_eq!(path_cost(&ab, &cdab, &path), 3);
+        assert_eq!(longest_gap(&ab, &cdab, &path), 1);
+        // without a binding constraint the cost is the levenshtein distance
+        let (kitten, sitting) = (Graphemes::from("kitten"), Graphemes::from("sitting"));
+        let path = alignment_max_gap(&kitten, &sitting, 1, 7).unwrap();
+        assert_eq!(path_cost(&kitten, &sitting, &path), crate::metrics::levenshtein_distance(&kitten, &sitting, 1));
+        assert_eq!(alignment_max_gap(&kitten, &sitting, 1, 0), None);
+        assert_eq!(alignment_max_gap(&kitten, &Graphemes::from("sittin"), 1, 0).map(|path| path.len()), Some(7));
+        assert_eq!(alignment_max_gap(&Graphemes::from(""), &Graphemes::from(""), 1, 0), Some(vec![(0, 0)]));
+    }
+
+    #[test]
+    fn alignment_ops_test() {
+        let ops = alignment_ops(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京市的烤鸭"), 1);
+        assert_eq!(ops.len(), 6);
+        // "市的" is a single gap of two
+        assert_eq!(ops.iter().filter(|&&op| op == AlignmentOp::Insert { gap_open: true }).count(), 1);
+        assert_eq!(ops.iter().filter(|&&op| op == AlignmentOp::Insert { gap_open: false }).count(), 1);
+
+        let ops = alignment_ops(&Graphemes::from("abcdef"), &Graphemes::from("abf"), 1);
+        assert_eq!(ops.iter().filter(|op| matches!(op, AlignmentOp::Delete { .. })).count(), 3);
+        assert_eq!(ops.iter().filter(|&&op| op == AlignmentOp::Delete { gap_open: true }).count(), 1);
+        // two separate gaps open twice
+        let ops = alignment_ops(&Graphemes::from("abc"), &Graphemes::from("xaybc"), 1);
+        assert_eq!(ops, vec![
+            AlignmentOp::Insert { gap_open: true },
+            AlignmentOp::Match,
+            AlignmentOp::Insert { gap_open: true },
+            AlignmentOp::Match,
+            AlignmentOp::Match,
+        ]);
+        assert!(alignment_ops(&Graphemes::from(""), &Graphemes::from(""), 1).is_empty());
+    }
+
+    #[test]
+    fn alignment_index_pairs_test() {
+        // the "r" of "cart" is a gap in "cat"
+        assert_eq!(alignment_index_pairs(&Graphemes::from("cat"), &Graphemes::from("cart"), 1),
+                   vec![(Some(0), Some(0)), (Some(1), Some(1)), (None, Some(2)), (Some(2), Some(3))]);
+        assert_eq!(alignment_index_pairs(&Graphemes::from("cart"), &Graphemes::from("cat"), 1),
+                   vec![(Some(0), Some(0)), (Some(1), Some(1)), (Some(2), None), (Some(3), Some(2))]);
+        // indices are grapheme indices, not byte offsets
+        assert_eq!(alignment_index_pairs(&Graphemes::from("a̐é"), &Graphemes::from("xa̐é"), 1),
+                   vec![(None, Some(0)), (Some(0), Some(1)), (Some(1), Some(2))]);
+        assert!(alignment_index_pairs(&Graphemes::from(""), &Graphemes::from(""), 1).is_empty());
+    }
+
+    #[test]
+    fn levenshtein_distance_reexport_test() {
+        let (kitten, sitting) = (Graphemes::from("kitten"), Graphemes::from("sitting"));
+        assert_eq!(crate::levenshtein_distance(&kitten, &sitting, 1), crate::metrics::levenshtein_distance(&kitten, &sitting, 1));
+        // the alignment follows the same cost matrix
+        for &sub_cost in [0, 1, 2, 3].iter() {
+            assert_eq!(cost_contributions(&kitten, &sitting, sub_cost).iter().sum::<usize>(), crate::levenshtein_distance(&kitten, &sitting, sub_cost));
+        }
+    }
+
+    #[test]
+    fn alignment_tie_break_test() {
+        let (abc, axc) = (Graphemes::from("abc"), Graphemes::from("axc"));
+        assert_eq!(alignment_path(&abc, &axc, 1), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+        // substituting "b" with "x" costs as much as deleting and inserting it
+        assert_eq!(alignment_path(&abc, &axc, 2), vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+        assert_eq!(alignment_path_with(&abc, &axc, &AlignmentConfig { sub_cost: 2, prefer_substitution: false }),
+                   vec![(0, 0), (1, 1), (1, 2), (2, 2), (3, 3)]);
+        // gaps come as early as possible
+        assert_eq!(alignment_path(&Graphemes::from("ab"), &Graphemes::from("ba"), 1), vec![(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(alignment_path(&Graphemes::from("ab"), &Graphemes::from("ba"), 2), vec![(0, 0), (0, 1), (1, 2), (2, 2)]);
+        assert_eq!(alignment_path(&Graphemes::from("aa"), &Graphemes::from("a"), 1), vec![(0, 0), (1, 0), (2, 1)]);
+        assert_eq!(alignment_path(&Graphemes::from("a"), &Graphemes::from("b"), 3), vec![(0, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn alignment_config_test() {
+        let (graphemes1, graphemes2) = (Graphemes::from("a"), Graphemes::from("b"));
+        let gaps = AlignmentConfig { sub_cost: 2, prefer_substitution: false };
+        let diagonal = AlignmentConfig { sub_cost: 2, prefer_substitution: true };
+        assert_eq!(alignment_path_with(&graphemes1, &graphemes2, &gaps), vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(alignment_path_with(&graphemes1, &graphemes2, &diagonal), vec![(0, 0), (1, 1)]);
+        assert_eq!(alignment_strings_with(&graphemes1, &graphemes2, &gaps, " "), [Graphemes::from(" a"), Graphemes::from("b ")]);
+        assert_eq!(alignment_strings_with(&graphemes1, &graphemes2, &diagonal, " "), [Graphemes::from("a"), Graphemes::from("b")]);
+        // a cheaper substitution wins either way, a dearer one never does
+        let (kitten, sitting) = (Graphemes::from("kitten"), Graphemes::from("sitting"));
+        assert_eq!(alignment_path_with(&kitten, &sitting, &AlignmentConfig { sub_cost: 1, prefer_substitution: false }),
+                   alignment_path_with(&kitten, &sitting, &AlignmentConfig { sub_cost: 1, prefer_substitution: true }));
+        assert_eq!(alignment_path_with(&graphemes1, &graphemes2, &AlignmentConfig { sub_cost: 3, prefer_substitution: true }),
+                   vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(AlignmentConfig::default(), AlignmentConfig { sub_cost: 1, prefer_substitution: true });
+        assert_eq!(alignment_path(&kitten, &sitting, 2), alignment_path_with(&kitten, &sitting, &AlignmentConfig { sub_cost: 2, ..AlignmentConfig::default() }));
+    }
+
+    #[test]
+    fn edit_operations_test() {
+        let ops = edit_operations(&Graphemes::from("book"), &Graphemes::from("back"), 1);
+        assert_eq!(ops.iter().filter(|op| matches!(op, EditOp::Substitute(..))).count(), 2);
+        assert_eq!(ops.iter().filter(|op| matches!(op, EditOp::Match(..))).count(), 2);
+        assert_eq!(ops, vec![EditOp::Match(0, 0), EditOp::Substitute(1, 1), EditOp::Substitute(2, 2), EditOp::Match(3, 3)]);
+        assert_eq!(edit_operations(&Graphemes::from("cart"), &Graphemes::from("cat"), 1),
+                   vec![EditOp::Match(0, 0), EditOp::Match(1, 1), EditOp::Delete(2), EditOp::Match(3, 2)]);
+        // the edits account for the distance
+        let (graphemes1, graphemes2) = (Graphemes::from("北京烤鸭"), Graphemes::from("南京市的烤鸭"));
+        let cost : usize = edit_operations(&graphemes1, &graphemes2, 1).iter().map(|op| match op {
+            EditOp::Match(..) => 0,
+            _ => 1,
+        }).sum();
+        assert_eq!(cost, metrics::levenshtein_distance(&graphemes1, &graphemes2, 1));
+        assert!(edit_operations(&Graphemes::from(""), &Graphemes::from(""), 1).is_empty());
+    }
+
+    #[test]
+    fn alignment_path_with_secondary_test() {
+        let (graphemes1, graphemes2) = (Graphemes::from("aabb"), Graphemes::from("ab"));
+        let path = |objective| alignment_path_with_secondary(&graphemes1, &graphemes2, 1, objective);
+        // every objective picks a different one of the optimal alignments deleting an "a" and a "b"
+        assert_eq!(path(Secondary::EarliestGaps), vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2)]);
+        assert_eq!(path(Secondary::LatestGaps), vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+        assert_eq!(path(Secondary::FewestGapRuns), vec![(0, 0), (1, 1), (2, 1), (3, 1), (4, 2)]);
+        assert_eq!(path(Secondary::LongestMatchRun), vec![(0, 0), (1, 0), (2, 1), (3, 2), (4, 2)]);
+
+        let objectives = [Secondary::EarliestGaps, Secondary::LatestGaps, Secondary::FewestGapRuns, Secondary::LongestMatchRun];
+        for &(word1, word2) in [("kitten", "sitting"), ("北京烤鸭", "烤鸭北京"), ("", "abc"), ("abc", ""), ("", "")].iter() {
+            let (graphemes1, graphemes2) = (Graphemes::from(word1), Graphemes::from(word2));
+            let distance = crate::metrics::levenshtein_distance(&graphemes1, &graphemes2, 2);
+            for &objective in objectives.iter() {
+                let path = alignment_path_with_secondary(&graphemes1, &graphemes2, 2, objective);
+                assert_eq!(path[0], (0, 0));
+                assert_eq!(path[path.len() - 1], (graphemes1.len(), graphemes2.len()));
+                let cost : usize = alignment_steps(&graphemes1, &graphemes2, &path).iter().map(|step| match step {
+                    AlignmentStep::Match => 0,
+                    AlignmentStep::Substitute => 2,
+                    _ => 1,
+                }).sum();
+                assert_eq!(cost, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn alignment_ambiguity_test() {
+        assert_eq!(alignment_ambiguity(&Graphemes::from("kitten"), &Graphemes::from("kitten"), 1), 1);
+        assert_eq!(alignment_ambiguity(&Graphemes::from(""), &Graphemes::from(""), 1), 1);
+        assert_eq!(alignment_ambiguity(&Graphemes::from(""), &Graphemes::from("abc"), 1), 1);
+        // "ab" → "ba": substitute both, or delete and insert either letter on either side
+        assert_eq!(alignment_ambiguity(&Graphemes::from("ab"), &Graphemes::from("ba"), 1), 3);
+        // the two "a" can be matched with any two of the four
+        assert_eq!(alignment_ambiguity(&Graphemes::from("aaaa"), &Graphemes::from("aa"), 1), 6);
+        // with no common grapheme and substitutions costing 2, every path is optimal: the Delannoy number D(5, 5)
+        assert_eq!(alignment_ambiguity(&Graphemes::from("abcde"), &Graphemes::from("vwxyz"), 2), 1683);
+        // the count saturates instead of overflowing
+        let long1 = Graphemes::from("abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz");
+        let long2 = Graphemes::from("ABCDEFGHIJKLMNOPQRSTUVWXYZABCDEFGHIJKLMNOPQRSTUVWXYZ");
+        assert_eq!(alignment_ambiguity(&long1, &long2, 2), usize::MAX);
+    }
+
+    #[test]
+    fn aligned_pairs_test() {
+        assert_eq!(aligned_pairs(&Graphemes::from("dog"), &Graphemes::from("woof"), 1),
+                   vec![(None, Some("w")), (Some("d"), Some("o")), (Some("o"), Some("o")), (Some("g"), Some("f"))]);
+        assert_eq!(aligned_pairs(&Graphemes::from("北京烤鸭"), &Graphemes::from("北鸭"), 1),
+                   vec![(Some("北"), Some("北")), (Some("京"), None), (Some("烤"), None), (Some("鸭"), Some("鸭"))]);
+        // a padding character in the input is an ordinary grapheme
+        assert_eq!(aligned_pairs(&Graphemes::from("a-"), &Graphemes::from("a"), 1), vec![(Some("a"), Some("a")), (Some("-"), None)]);
+        assert!(aligned_pairs(&Graphemes::from(""), &Graphemes::from(""), 1).is_empty());
+    }
+
+    #[test]
+    fn side_by_side_test() {
+        let lines = side_by_side(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1, 4);
+        let pair = |left : &str, right : &str| (String::from(left), String::from(right));
+        assert_eq!(lines, vec![pair("kitt", "sitt"), pair("en░ ", "ing ")]);
+        // a wide grapheme against a narrow one takes two columns on both sides
+        assert_eq!(side_by_side(&Graphemes::from("ab"), &Graphemes::from("北b"), 1, 3), vec![pair("a b", "北b")]);
+        assert_eq!(side_by_side(&Graphemes::from("a北"), &Graphemes::from("北"), 1, 4), vec![pair("a北 ", "░北 ")]);
+        // a column wider than the line is put on a line of its own
+        assert_eq!(side_by_side(&Graphemes::from("北京"), &Graphemes::from("北京"), 1, 1), vec![pair("北", "北"), pair("京", "京")]);
+        assert!(side_by_side(&Graphemes::from(""), &Graphemes::from(""), 1, 4).is_empty());
+        // a gap is told apart from a hyphen
+        assert_eq!(side_by_side(&Graphemes::from("a-b"), &Graphemes::from("ab"), 1, 3), vec![pair("a-b", "a░b")]);
+        // lines are broken like Graphemes::wrap, at spaces on both sides
+        assert_eq!(side_by_side(&Graphemes::from("we can see"), &Graphemes::from("we cab see"), 1, 7),
+                   vec![pair("we can ", "we cab "), pair("see    ", "see    ")]);
+        assert_eq!(side_by_side(&Graphemes::from("ab\ncd"), &Graphemes::from("ab\ncd"), 1, 3),
+                   vec![pair("ab ", "ab "), pair("cd ", "cd ")]);
+    }
+
+    #[test]
+    fn align3_test() {
+        let (base, mine, theirs) = (Graphemes::from("abcdef"), Graphemes::from("abXcdef"), Graphemes::from("abcdeYf"));
+        let columns = align3(&base, &mine, &theirs, 1);
+        // every column holds one grapheme or gap of each sequence, and the sequences read back in order
+        assert_eq!(columns.len(), 8);
+        assert_eq!(columns.iter().filter_map(|column| column.0).collect::<String>(), "abcdef");
+        assert_eq!(columns.iter().filter_map(|column| column.1).collect::<String>(), "abXcdef");
+        assert_eq!(columns.iter().filter_map(|column| column.2).collect::<String>(), "abcdeYf");
+        // both changes apply without conflict
+        let merged : Option<String> = columns.iter().map(|&(base, mine, theirs)| {
+            if mine == base { Some(theirs) } else if theirs == base || mine == theirs { Some(mine) } else { None }
+        }).collect::<Option<Vec<Option<&str>>>>().map(|graphemes| graphemes.into_iter().flatten().collect());
+        assert_eq!(merged, Some(String::from("abXcdeYf")));
+
+        let columns = align3(&Graphemes::from("北京"), &Graphemes::from("南京"), &Graphemes::from("北京烤鸭"), 1);
+        assert_eq!(columns, vec![
+            (Some("北"), Some("南"), Some("北")),
+            (Some("京"), Some("京"), Some("京")),
+            (None, None, Some("烤")),
+            (None, None, Some("鸭")),
+        ]);
+        assert!(align3(&Graphemes::from(""), &Graphemes::from(""), &Graphemes::from(""), 1).is_empty());
+    }
+
+    #[test]
+    fn case_only_differences_test() {
+        let (hello, lower_hello) = (Graphemes::from("Hello"), Graphemes::from("hello"));
+        assert_eq!(case_only_differences(&hello, &lower_hello, 1), 1);
+        assert_eq!(difference_positions(&hello, &lower_hello, 1).len() - case_only_differences(&hello, &lower_hello, 1), 0);
+        let (actual, predict) = (Graphemes::from("Hello World"), Graphemes::from("HELLO wurld"));
+        assert_eq!(case_only_differences(&actual, &predict, 1), 5);
+        assert_eq!(difference_positions(&actual, &predict, 1).len(), 6);
+        // accented graphemes compare as a whole
+        assert_eq!(case_only_differences(&Graphemes::from("e\u{301}"), &Graphemes::from("E\u{301}"), 1), 1);
+        assert_eq!(case_only_differences(&Graphemes::from("北京"), &Graphemes::from("南京"), 1), 0);
+        assert_eq!(case_only_differences(&Graphemes::from("same"), &Graphemes::from("same"), 1), 0);
+    }
+
+    #[test]
+    fn cost_contributions_test() {
+        let (superman, batman) = (Graphemes::from("superman"), Graphemes::from("batman"));
+        for &sub_cost in [0, 1, 2, 3].iter() {
+            let costs = cost_contributions(&superman, &batman, sub_cost);
+            assert_eq!(costs.iter().sum::<usize>(), crate::metrics::levenshtein_distance(&superman, &batman, sub_cost));
+            // every column of the alignment has a cost
+            assert_eq!(costs.len(), alignment_index_pairs(&superman, &batman, sub_cost).len());
+        }
+        // "man" is matched at the end
+        assert_eq!(cost_contributions(&superman, &batman, 1)[5..], [0, 0, 0]);
+        assert!(cost_contributions(&Graphemes::from(""), &Graphemes::from(""), 1).is_empty());
+        assert_eq!(cost_contributions(&Graphemes::from(""), &Graphemes::from("ab"), 1), vec![1, 1]);
+    }
+
+    #[test]
+    fn difference_positions_test() {
+        let kitten = Graphemes::from("kitten");
+        let sitting = Graphemes::from("sitting");
+        let [aligned1, aligned2] = alignment_strings(&kitten, &sitting, 1, " ");
+        // kitten_
+        // sitting
+        assert_eq!((aligned1.to_string(), aligned2.to_string()), (String::from("kitten "), String::from("sitting")));
+        assert_eq!(difference_positions(&kitten, &sitting, 1), vec![0, 4, 6]);
+        assert!(difference_positions(&kitten, &kitten, 1).is_empty());
+        assert_eq!(difference_positions(&Graphemes::from(""), &Graphemes::from("ab"), 1), vec![0, 1]);
+    }
+
+    #[test]
+    fn max_match_ignoring_test() {
+        let chinese_dictionary = chinese_dictionary();
+        let ignorable : HashSet<&str> = ["\u{200B}", "\u{FEFF}"].iter().cloned().collect();
+        let sentence = Graphemes::from("\u{FEFF}他特别喜欢北京\u{200B}烤鸭\u{200B}");
+        assert_eq!(max_match_ignoring(&sentence, &chinese_dictionary, &ignorable, IgnorableMode::Strip),
+                   Graphemes::from("他 特别 喜欢 北京烤鸭"));
+        assert_eq!(max_match_ignoring(&sentence, &chinese_dictionary, &ignorable, IgnorableMode::PassThrough),
+                   Graphemes::from("\u{FEFF}他 特别 喜欢 北京\u{200B}烤鸭\u{200B}"));
+        // without ignorables both modes agree with max_match
+        let plain = Graphemes::from("他特别喜欢english");
+        for &mode in [IgnorableMode::Strip, IgnorableMode::PassThrough].iter() {
+            assert_eq!(max_match_ignoring(&plain, &chinese_dictionary, &ignorable, mode), max_match(&plain, &chinese_dictionary));
+        }
+        let only_ignorable = Graphemes::from("\u{200B}");
+        assert!(max_match_ignoring(&only_ignorable, &chinese_dictionary, &ignorable, IgnorableMode::Strip).is_empty());
+        assert_eq!(max_match_ignoring(&only_ignorable, &chinese_dictionary, &ignorable, IgnorableMode::PassThrough), only_ignorable);
+    }
+}
@@ -1,61 +1,1273 @@
 pub use self::metrics::{
     levenshtein_distance,
+    cost_matrix,
+    levenshtein_distance_linear,
+    within_distance,
+    levenshtein_distance_bounded,
+    str_levenshtein,
+    levenshtein_ratio,
+    jaro_similarity,
+    jaro_winkler_similarity,
+    DistanceCache,
+    block_edit_distance,
+    levenshtein_display_width,
+    keyboard_levenshtein,
+    levenshtein_free_delete,
+    levenshtein_pattern,
+    GraphemeClass,
+    FlexPattern,
+    flex_distance,
+    levenshtein_stemmed,
+    jaccard_qgram,
+    ngram_cosine,
+    analyze,
+    DistanceResult,
+    typo_likelihood,
+    TypoClass,
+    common_prefix_len,
+    common_suffix_len,
+    circular_levenshtein,
     word_error_rate,
-    word_accuracy
+    word_error_rate_with,
+    word_error_rate_detailed,
+    WerReport,
+    min_wer_over_references,
+    dual_error_rates,
+    character_error_rate,
+    word_accuracy,
+    word_move_distance,
+    boundary_prf,
+    segmentation_agreement,
+    ConfusionMatrix
 };
+#[cfg(feature = "rand")]
+pub use self::metrics::wer_bootstrap_ci;
 
 mod metrics {
     use itertools::Itertools;
-    use len_trait::len::Len;
-    use std::ops::Index;
     use std::cmp::min;
-    use crate::graphemes_struct::Graphemes;
+    use std::ops::Index;
+    use len_trait::len::{Len, Empty};
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    use crate::graphemes_struct::{Graphemes, OwnedGraphemes, grapheme_width};
+    use crate::{Sequence, alignment_path, alignment_steps, AlignmentStep};
+    use crate::tokenizer::Tokenizer;
+    use crate::stemmer::Stemmer;
+    #[cfg(feature = "rand")]
+    use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+    /// Calculates the levenshtein distance between two words
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_distance;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(levenshtein_distance(&Graphemes::from("book"), &Graphemes::from("back"), 1), 2);
+    /// assert_eq!(levenshtein_distance(&Graphemes::from("back"), &Graphemes::from("book"), 1), 2);
+    /// assert_eq!(levenshtein_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1), 3);
+    /// ```
+    pub fn levenshtein_distance<T>(graphemes1 : &T, graphemes2: &T, sub_cost : usize) -> usize
+        where T : Sequence + ?Sized {
+        cost_matrix(graphemes1, graphemes2, sub_cost)[graphemes1.len()][graphemes2.len()]
+    }
+
+    /// Returns the levenshtein cost matrix of two words, with `len1+1` rows and `len2+1` columns.
+    /// `matrix[row][col]` is the distance between the first `row` graphemes of `graphemes1`
+    /// and the first `col` graphemes of `graphemes2`, so the last cell holds the distance of the whole words.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`, one per row
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`, one per column
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::{cost_matrix, levenshtein_distance};
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let (kitten, sitting) = (Graphemes::from("kitten"), Graphemes::from("sitting"));
+    /// let matrix = cost_matrix(&kitten, &sitting, 1);
+    /// assert_eq!((matrix.len(), matrix[0].len()), (7, 8));
+    /// assert_eq!(matrix[6][7], levenshtein_distance(&kitten, &sitting, 1));
+    /// // "kitt" to "sitt"
+    /// assert_eq!(matrix[4][4], 1);
+    /// ```
+    pub fn cost_matrix<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<Vec<usize>>
+        where T : Sequence + ?Sized {
+        let num_rows = graphemes1.len() + 1;
+        let num_cols = graphemes2.len() + 1;
+        let mut recurrence_matrix : Vec<Vec<usize>> = vec![vec![0; num_cols]; num_rows];
+        // graphemes1 → row
+        // graphemes2 → column
+        for (row, cells) in recurrence_matrix.iter_mut().enumerate() {
+            cells[0] = row;
+        }
+        for (col, cell) in recurrence_matrix[0].iter_mut().enumerate() {
+            *cell = col;
+        }
+
+        for (row, col) in (1..num_rows).cartesian_product(1..num_cols) {
+            recurrence_matrix[row][col] = min(min(
+                recurrence_matrix[row-1][col]+1,
+                recurrence_matrix[row][col-1]+1
+            ),  recurrence_matrix[row-1][col-1] + if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost})
+        }
+        recurrence_matrix
+    }
+
+    /// Calculates the levenshtein distance between two words like `levenshtein_distance`, but keeps only
+    /// two rows of the cost matrix instead of all of them, so memory grows with the length of `graphemes2` alone.
+    /// Prefer it for long inputs when only the distance is needed: two texts of 10,000 graphemes need
+    /// two rows of 10,001 cells here instead of a matrix of about 100 million cells.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_distance_linear;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(levenshtein_distance_linear(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1), 3);
+    /// let (long1, long2) = ("己所不欲勿施于人".repeat(100), "不患人之不己知患不知人也".repeat(100));
+    /// let distance = levenshtein_distance_linear(&Graphemes::from(long1.as_str()), &Graphemes::from(long2.as_str()), 1);
+    /// assert!(distance >= 400 && distance <= 1200);
+    /// ```
+    pub fn levenshtein_distance_linear<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> usize
+        where T : Sequence + ?Sized {
+        let num_cols = graphemes2.len() + 1;
+        let mut previous_row : Vec<usize> = (0..num_cols).collect();
+        let mut current_row : Vec<usize> = vec![0; num_cols];
+        for row in 1..graphemes1.len()+1 {
+            current_row[0] = row;
+            for col in 1..num_cols {
+                current_row[col] = min(min(
+                    previous_row[col]+1,
+                    current_row[col-1]+1
+                ), previous_row[col-1] + if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost})
+            }
+            std::mem::swap(&mut previous_row, &mut current_row);
+        }
+        previous_row[num_cols-1]
+    }
+
+    /// Returns whether the levenshtein distance between two words is at most `k`, without computing it exactly.
+    /// Rows of the cost matrix are computed one at a time and the answer is returned as soon as every cell of a row
+    /// exceeds `k`, or a cell of a row can reach the end within `k` by editing every remaining grapheme.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    /// * `k` - Largest accepted distance
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::within_distance;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert!(within_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1, 3));
+    /// assert!(!within_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1, 2));
+    /// ```
+    pub fn within_distance<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, k : usize) -> bool
+        where T : Sequence + ?Sized {
+        let (len1, len2) = (graphemes1.len(), graphemes2.len());
+        if len1.abs_diff(len2) > k {
+            return false;
+        }
+        if len1 + len2 <= k {
+            return true;
+        }
+        let mut previous_row : Vec<usize> = (0..=len2).collect();
+        for row in 1..=len1 {
+            let mut current_row = vec![row; len2 + 1];
+            for col in 1..=len2 {
+                let step_cost = if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost};
+                current_row[col] = min(min(previous_row[col] + 1, current_row[col-1] + 1), previous_row[col-1] + step_cost);
+            }
+            // every path to the end goes through this row
+            if current_row.iter().all(|&cost| cost > k) {
+                return false;
+            }
+            // finishing by substituting the remaining graphemes pairwise and inserting or deleting the rest
+            let finish_cost = |col : usize| {
+                let (rest1, rest2) = (len1 - row, len2 - col);
+                min(rest1, rest2) * min(sub_cost, 2) + rest1.abs_diff(rest2)
+            };
+            if current_row.iter().enumerate().any(|(col, &cost)| cost + finish_cost(col) <= k) {
+                return true;
+            }
+            previous_row = current_row;
+        }
+        previous_row[len2] <= k
+    }
+
+    /// Calculates the levenshtein distance between two words if it is at most `max`, or returns `None`.
+    /// Words whose lengths differ by more than `max` are rejected without computing anything, and rows
+    /// of the cost matrix are computed one at a time, stopping as soon as every cell of a row exceeds `max`.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    /// * `max` - Largest distance of interest
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_distance_bounded;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(levenshtein_distance_bounded(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1, 5), Some(3));
+    /// assert_eq!(levenshtein_distance_bounded(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1, 2), None);
+    /// ```
+    pub fn levenshtein_distance_bounded<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize, max : usize) -> Option<usize>
+        where T : Sequence + ?Sized {
+        let (len1, len2) = (graphemes1.len(), graphemes2.len());
+        if len1.abs_diff(len2) > max {
+            return None;
+        }
+        let mut previous_row : Vec<usize> = (0..=len2).collect();
+        for row in 1..=len1 {
+            let mut current_row = vec![row; len2 + 1];
+            for col in 1..=len2 {
+                let step_cost = if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost};
+                current_row[col] = min(min(previous_row[col] + 1, current_row[col-1] + 1), previous_row[col-1] + step_cost);
+            }
+            // every path to the end goes through this row
+            if current_row.iter().all(|&cost| cost > max) {
+                return None;
+            }
+            previous_row = current_row;
+        }
+        Some(previous_row[len2]).filter(|&distance| distance <= max)
+    }
+
+    /// Calculates the levenshtein distance between the graphemes of two strings
+    ///
+    /// # Arguments
+    /// * `string1` - String to compare with `string2`
+    /// * `string2` - String to compare with `string1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::str_levenshtein;
+    /// assert_eq!(str_levenshtein("book", "back", 1), 2);
+    /// assert_eq!(str_levenshtein("kitten", "sitting", 1), 3);
+    /// assert_eq!(str_levenshtein("北京烤鸭", "南京烤鸭", 1), 1);
+    /// // "é" written with a combining accent is a single grapheme
+    /// assert_eq!(str_levenshtein("cafe\u{301}", "cafe", 1), 1);
+    /// ```
+    pub fn str_levenshtein(string1 : &str, string2 : &str, sub_cost : usize) -> usize {
+        levenshtein_distance(&Graphemes::from(string1), &Graphemes::from(string2), sub_cost)
+    }
+
+    /// Calculates the similarity of two words as 1 - levenshtein distance / length of the longer word,
+    /// where substitutions cost 1, so the ratio lies between 0.0 for entirely different words and 1.0 for equal words.
+    /// Two empty words are equal and have a ratio of 1.0 rather than dividing by zero.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_ratio;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(levenshtein_ratio(&Graphemes::from("book"), &Graphemes::from("back")), 0.5);
+    /// assert_eq!(levenshtein_ratio(&Graphemes::from(""), &Graphemes::from("")), 1.0);
+    /// ```
+    pub fn levenshtein_ratio(graphemes1 : &Graphemes, graphemes2 : &Graphemes) -> f64 {
+        let longer_len = graphemes1.len().max(graphemes2.len());
+        if longer_len == 0 {
+            return 1.0;
+        }
+        1.0 - levenshtein_distance(graphemes1, graphemes2, 1) as f64 / longer_len as f64
+    }
+
+    /// Calculates the Jaro similarity of two words, between 0.0 and 1.0. Graphemes match when they are equal and
+    /// no further apart than max(len1, len2) / 2 - 1 positions, and half of the matches out of order count as transpositions.
+    /// Two empty words score 1.0.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::jaro_similarity;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// // six matches and one transposition
+    /// let similarity = jaro_similarity(&Graphemes::from("martha"), &Graphemes::from("marhta"));
+    /// assert!((similarity - 0.944).abs() < 0.001);
+    /// ```
+    pub fn jaro_similarity(graphemes1 : &Graphemes, graphemes2 : &Graphemes) -> f64 {
+        let (len1, len2) = (graphemes1.len(), graphemes2.len());
+        if len1 == 0 && len2 == 0 {
+            return 1.0;
+        }
+        let window = (len1.max(len2) / 2).saturating_sub(1);
+        let mut matched1 = vec![false; len1];
+        let mut matched2 = vec![false; len2];
+        for i in 0..len1 {
+            let window_end = (i + window + 1).min(len2);
+            if let Some(j) = (i.saturating_sub(window)..window_end).find(|&j| !matched2[j] && graphemes1[i] == graphemes2[j]) {
+                matched1[i] = true;
+                matched2[j] = true;
+            }
+        }
+        let matches1 : Vec<&str> = (0..len1).filter(|&i| matched1[i]).map(|i| graphemes1[i]).collect();
+        let matches2 : Vec<&str> = (0..len2).filter(|&j| matched2[j]).map(|j| graphemes2[j]).collect();
+        let num_matches = matches1.len() as f64;
+        if num_matches == 0.0 {
+            return 0.0;
+        }
+        let transpositions = matches1.iter().zip(matches2.iter()).filter(|(grapheme1, grapheme2)| grapheme1 != grapheme2).count() as f64 / 2.0;
+        (num_matches / len1 as f64 + num_matches / len2 as f64 + (num_matches - transpositions) / num_matches) / 3.0
+    }
+
+    /// Calculates the Jaro-Winkler similarity of two words, raising the Jaro similarity of words sharing a prefix:
+    /// jaro + prefix length * `prefix_scale` * (1 - jaro), where the common prefix counts at most 4 graphemes.
+    /// `prefix_scale` is clamped between 0.0 and 0.25 so the similarity never exceeds 1.0.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `prefix_scale` - Boost given to each grapheme of the common prefix, usually 0.1
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::jaro_winkler_similarity;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let similarity = jaro_winkler_similarity(&Graphemes::from("martha"), &Graphemes::from("marhta"), 0.1);
+    /// assert!((similarity - 0.961).abs() < 0.001);
+    /// ```
+    pub fn jaro_winkler_similarity(graphemes1 : &Graphemes, graphemes2 : &Graphemes, prefix_scale : f64) -> f64 {
+        let jaro = jaro_similarity(graphemes1, graphemes2);
+        let prefix_len = common_prefix_len(graphemes1, graphemes2).min(4) as f64;
+        jaro + prefix_len * prefix_scale.clamp(0.0, 0.25) * (1.0 - jaro)
+    }
+
+    /// (graphemes1, graphemes2, sub_cost) of a cached distance. The graphemes are kept separate rather than
+    /// joined into strings, since graphemes with the same text but different boundaries have different distances.
+    type CacheKey = (OwnedGraphemes, OwnedGraphemes, usize);
+
+    /// Memoizes levenshtein distances, evicting the least recently used entry once `capacity` entries are stored
+    #[derive(Debug, Clone)]
+    pub struct DistanceCache {
+        capacity : usize,
+        /// key → (distance, last use)
+        entries : HashMap<CacheKey, (usize, u64)>,
+        /// last use → key, oldest first
+        recency : BTreeMap<u64, CacheKey>,
+        clock : u64,
+    }
+
+    impl DistanceCache {
+        pub fn new(capacity : usize) -> DistanceCache {
+            DistanceCache {
+                capacity,
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                clock: 0,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.entries.is_empty()
+        }
+
+        /// Returns whether the distance between the graphemes is cached, without counting as a use
+        pub fn contains(&self, graphemes1 : &Graphemes, graphemes2 : &Graphemes, sub_cost : usize) -> bool {
+            self.entries.contains_key(&(graphemes1.to_owned(), graphemes2.to_owned(), sub_cost))
+        }
+
+        /// Returns the levenshtein distance between two words, computing it only if it is not cached
+        ///
+        /// # Arguments
+        /// * `graphemes1` - Graphemes to compare with `graphemes2`
+        /// * `graphemes2` - Graphemes to compare with `graphemes1`
+        /// * `sub_cost` - Cost of substituting a character with another
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::metrics::DistanceCache;
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let mut cache = DistanceCache::new(100);
+        /// assert_eq!(cache.cached_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1), 3);
+        /// assert!(cache.contains(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1));
+        /// ```
+        pub fn cached_distance(&mut self, graphemes1 : &Graphemes, graphemes2 : &Graphemes, sub_cost : usize) -> usize {
+            self.clock += 1;
+            let key = (graphemes1.to_owned(), graphemes2.to_owned(), sub_cost);
+            if let Some((distance, last_use)) = self.entries.get_mut(&key) {
+                self.recency.remove(last_use);
+                *last_use = self.clock;
+                self.recency.insert(self.clock, key);
+                return *distance;
+            }
+            let distance = levenshtein_distance(graphemes1, graphemes2, sub_cost);
+            if self.capacity == 0 {
+                return distance;
+            }
+            if self.entries.len() == self.capacity {
+                if let Some((_, oldest)) = self.recency.pop_first() {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.entries.insert(key.clone(), (distance, self.clock));
+            self.recency.insert(self.clock, key);
+            distance
+        }
+    }
+
+    /// Levenshtein distance between two words along with its normalized similarity
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct DistanceResult {
+        /// Levenshtein distance
+        pub distance : usize,
+        /// `1 - distance / max_len` clamped to `[0.0, 1.0]`, or 1.0 when both words are empty
+        pub similarity : f64,
+        /// Length of the longer word
+        pub max_len : usize,
+    }
+
+    /// Calculates the levenshtein distance and normalized similarity of two words from a single cost matrix
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::analyze;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let result = analyze(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1);
+    /// assert_eq!((result.distance, result.max_len), (3, 7));
+    /// assert_eq!(result.similarity, 1.0 - 3.0 / 7.0);
+    /// ```
+    pub fn analyze<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> DistanceResult
+        where T : Sequence + ?Sized {
+        let distance = levenshtein_distance(graphemes1, graphemes2, sub_cost);
+        let max_len = graphemes1.len().max(graphemes2.len());
+        let similarity = if max_len == 0 {
+            1.0
+        } else {
+            (1.0 - distance as f64 / max_len as f64).max(0.0)
+        };
+        DistanceResult { distance, similarity, max_len }
+    }
+
+    /// How likely a word is a typo of another, as classified by `typo_likelihood`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TypoClass {
+        /// The words are identical
+        Exact,
+        /// The words are close enough for one to be a misspelling of the other
+        LikelyTypo,
+        /// The words are too different to be a misspelling
+        Unrelated,
+    }
+
+    /// Classifies how likely one word is a typo of the other from their Damerau-Levenshtein distance
+    /// (optimal string alignment: insertions, deletions, substitutions and swaps of adjacent graphemes all cost 1).
+    /// Words at distance 0 are `Exact`. Words at a distance of at most a quarter of the length of the longer word,
+    /// and at least 1, are a `LikelyTypo`: 1 edit up to 7 graphemes, 2 edits from 8 graphemes, 3 from 12 and so on.
+    /// Anything further apart is `Unrelated`.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::{typo_likelihood, TypoClass};
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(typo_likelihood(&Graphemes::from("recieve"), &Graphemes::from("receive")), TypoClass::LikelyTypo);
+    /// assert_eq!(typo_likelihood(&Graphemes::from("cat"), &Graphemes::from("elephant")), TypoClass::Unrelated);
+    /// ```
+    pub fn typo_likelihood<T>(graphemes1 : &T, graphemes2 : &T) -> TypoClass
+        where T : Sequence + ?Sized {
+        let distance = optimal_string_alignment_distance(graphemes1, graphemes2);
+        let max_typos = (graphemes1.len().max(graphemes2.len()) / 4).max(1);
+        match distance {
+            0 => TypoClass::Exact,
+            distance if distance <= max_typos => TypoClass::LikelyTypo,
+            _ => TypoClass::Unrelated,
+        }
+    }
+
+    /// Levenshtein distance with substitutions costing 1 where swapping two adjacent graphemes also costs 1,
+    /// as long as no grapheme is edited more than once
+    fn optimal_string_alignment_distance<T>(graphemes1 : &T, graphemes2 : &T) -> usize
+        where T : Sequence + ?Sized {
+        let num_rows = graphemes1.len() + 1;
+        let num_cols = graphemes2.len() + 1;
+        let mut recurrence_matrix : Vec<Vec<usize>> = vec![vec![0; num_cols]; num_rows];
+        for (row, col) in (0..num_rows).cartesian_product(0..num_cols) {
+            recurrence_matrix[row][col] = if row == 0 || col == 0 {
+                row + col
+            } else {
+                let sub_cost = if graphemes1[row-1] == graphemes2[col-1] {0} else {1};
+                let mut distance = min(min(
+                    recurrence_matrix[row-1][col] + 1,
+                    recurrence_matrix[row][col-1] + 1
+                ), recurrence_matrix[row-1][col-1] + sub_cost);
+                if row > 1 && col > 1 && graphemes1[row-1] == graphemes2[col-2] && graphemes1[row-2] == graphemes2[col-1] {
+                    distance = min(distance, recurrence_matrix[row-2][col-2] + 1);
+                }
+                distance
+            };
+        }
+        recurrence_matrix[num_rows-1][num_cols-1]
+    }
+
+    /// Returns the number of leading graphemes two words have in common
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::common_prefix_len;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(common_prefix_len(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京大学")), 2);
+    /// ```
+    pub fn common_prefix_len<T>(graphemes1 : &T, graphemes2 : &T) -> usize
+        where T : Sequence + ?Sized {
+        let max_len = min(graphemes1.len(), graphemes2.len());
+        (0..max_len).find(|&i| graphemes1[i] != graphemes2[i]).unwrap_or(max_len)
+    }
+
+    /// Returns the number of trailing graphemes two words have in common
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::common_suffix_len;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(common_suffix_len(&Graphemes::from("北京烤鸭"), &Graphemes::from("烤鸭")), 2);
+    /// ```
+    pub fn common_suffix_len<T>(graphemes1 : &T, graphemes2 : &T) -> usize
+        where T : Sequence + ?Sized {
+        let (len1, len2) = (graphemes1.len(), graphemes2.len());
+        let max_len = min(len1, len2);
+        (0..max_len).find(|&i| graphemes1[len1 - 1 - i] != graphemes2[len2 - 1 - i]).unwrap_or(max_len)
+    }
+
+    /// Calculates the levenshtein distance between two cyclic words, i.e. the minimum distance
+    /// between `graphemes1` and any rotation of `graphemes2`.
+    /// Stops early once a rotation reaches the length difference, which no rotation can beat.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes whose rotations are compared with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::{circular_levenshtein, levenshtein_distance};
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(levenshtein_distance(&Graphemes::from("abcd"), &Graphemes::from("cdab"), 1), 4);
+    /// assert_eq!(circular_levenshtein(&Graphemes::from("abcd"), &Graphemes::from("cdab"), 1), 0);
+    /// ```
+    pub fn circular_levenshtein<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> usize
+        where T : Sequence + ?Sized {
+        let lower_bound = graphemes1.len().abs_diff(graphemes2.len());
+        let mut min_distance = levenshtein_distance(graphemes1, graphemes2, sub_cost);
+        for offset in 1..graphemes2.len() {
+            if min_distance == lower_bound {
+                break;
+            }
+            let rotation1 = Rotation { sequence: graphemes1, offset: 0 };
+            let rotation2 = Rotation { sequence: graphemes2, offset };
+            min_distance = min(min_distance, levenshtein_distance(&rotation1, &rotation2, sub_cost));
+        }
+        min_distance
+    }
+
+    /// View of `sequence` rotated left by `offset`
+    struct Rotation<'s, T : ?Sized> {
+        sequence : &'s T,
+        offset : usize,
+    }
+
+    impl<'s, T : Sequence + ?Sized> Empty for Rotation<'s, T> {
+        fn is_empty(&self) -> bool {
+            self.sequence.len() == 0
+        }
+    }
+
+    impl<'s, T : Sequence + ?Sized> Len for Rotation<'s, T> {
+        fn len(&self) -> usize {
+            self.sequence.len()
+        }
+    }
+
+    impl<'s, T : Sequence + ?Sized> Index<usize> for Rotation<'s, T> {
+        type Output = T::Item;
+
+        fn index(&self, index : usize) -> &T::Item {
+            &self.sequence[(self.offset + index) % self.sequence.len()]
+        }
+    }
+
+    /// Calculates the number of edit blocks between two words, where a run of consecutive
+    /// insertions, deletions or substitutions along the optimal alignment counts as one edit
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::block_edit_distance;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(block_edit_distance(&Graphemes::from("distance"), &Graphemes::from("dist  ance"), 1), 1);
+    /// ```
+    pub fn block_edit_distance<T>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> usize
+        where T : Sequence + ?Sized {
+        let path = alignment_path(graphemes1, graphemes2, sub_cost);
+        alignment_steps(graphemes1, graphemes2, &path).into_iter()
+            .dedup()
+            .filter(|&step| step != AlignmentStep::Match)
+            .count()
+    }
+
+    /// Calculates the levenshtein distance between two words weighted by display width.
+    /// Inserting or deleting a wide grapheme (e.g. CJK) costs 2 and a narrow one costs 1.
+    /// Substituting costs the width of the wider of the two graphemes.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_display_width;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(levenshtein_display_width(&Graphemes::from("北京"), &Graphemes::from("北")), 2);
+    /// assert_eq!(levenshtein_display_width(&Graphemes::from("book"), &Graphemes::from("boo")), 1);
+    /// ```
+    pub fn levenshtein_display_width(graphemes1 : &Graphemes, graphemes2 : &Graphemes) -> usize {
+        weighted_levenshtein_distance(graphemes1, graphemes2,
+                                      |grapheme| grapheme_width(grapheme),
+                                      |grapheme| grapheme_width(grapheme),
+                                      |grapheme1, grapheme2| grapheme_width(grapheme1).max(grapheme_width(grapheme2)))
+    }
+
+    /// Calculates a levenshtein distance for typos on a QWERTY keyboard where substituting a letter
+    /// costs half the distance between the two keys, capped at 1. Neighbouring keys cost about 0.5,
+    /// insertions, deletions and substitutions involving anything but lowercase ASCII letters cost 1.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::keyboard_levenshtein;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(keyboard_levenshtein(&Graphemes::from("cat"), &Graphemes::from("cst")), 0.5);
+    /// assert_eq!(keyboard_levenshtein(&Graphemes::from("cat"), &Graphemes::from("cpt")), 1.0);
+    /// assert_eq!(keyboard_levenshtein(&Graphemes::from("cat"), &Graphemes::from("cats")), 1.0);
+    /// ```
+    pub fn keyboard_levenshtein(graphemes1 : &Graphemes, graphemes2 : &Graphemes) -> f64 {
+        let sub_cost = |grapheme1 : &str, grapheme2 : &str| -> f64 {
+            match (qwerty_position(grapheme1), qwerty_position(grapheme2)) {
+                (Some((row1, col1)), Some((row2, col2))) => ((row1 - row2).hypot(col1 - col2) / 2.0).min(1.0),
+                _ => 1.0
+            }
+        };
+        let mut previous_row : Vec<f64> = (0..=graphemes2.len()).map(|col| col as f64).collect();
+        for row in 1..=graphemes1.len() {
+            let mut current_row = vec![row as f64; graphemes2.len() + 1];
+            for col in 1..=graphemes2.len() {
+                let substitution = if graphemes1[row-1] == graphemes2[col-1] { 0.0 } else { sub_cost(graphemes1[row-1], graphemes2[col-1]) };
+                current_row[col] = (previous_row[col] + 1.0)
+                    .min(current_row[col-1] + 1.0)
+                    .min(previous_row[col-1] + substitution);
+            }
+            previous_row = current_row;
+        }
+        previous_row[graphemes2.len()]
+    }
+
+    /// Position of a lowercase letter on a QWERTY keyboard as (row, column),
+    /// with lower rows shifted right by the stagger of a physical keyboard
+    fn qwerty_position(grapheme : &str) -> Option<(f64, f64)> {
+        const ROWS : [(&str, f64); 3] = [("qwertyuiop", 0.0), ("asdfghjkl", 0.25), ("zxcvbnm", 0.75)];
+        ROWS.iter().enumerate().find_map(|(row, &(keys, stagger))| {
+            keys.find(grapheme)
+                .filter(|_| grapheme.len() == 1)
+                .map(|col| (row as f64, col as f64 + stagger))
+        })
+    }
+
+    /// Calculates the levenshtein distance between two words where deleting a grapheme of `free_delete`
+    /// from `graphemes1` costs nothing. Inserting those graphemes into `graphemes1` still costs 1,
+    /// so the distance is not symmetric.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`, whose graphemes in `free_delete` are optional
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `sub_cost` - Cost of substituting a character with another
+    /// * `free_delete` - Graphemes deleted from `graphemes1` at no cost
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_free_delete;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// use std::collections::HashSet;
+    /// let free_delete : HashSet<&str> = ["-", "'"].iter().cloned().collect();
+    /// assert_eq!(levenshtein_free_delete(&Graphemes::from("e-mail"), &Graphemes::from("email"), 1, &free_delete), 0);
+    /// assert_eq!(levenshtein_free_delete(&Graphemes::from("email"), &Graphemes::from("e-mail"), 1, &free_delete), 1);
+    /// ```
+    pub fn levenshtein_free_delete(graphemes1 : &Graphemes, graphemes2 : &Graphemes, sub_cost : usize, free_delete : &HashSet<&str>) -> usize {
+        weighted_levenshtein_distance(graphemes1, graphemes2,
+                                      |_| 1,
+                                      |grapheme| if free_delete.contains(grapheme) { 0 } else { 1 },
+                                      |_, _| sub_cost)
+    }
+
+    /// A set of graphemes matched by one position of a pattern, similar to a regex character class
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum GraphemeClass {
+        /// Exactly this grapheme
+        Literal(String),
+        /// Any one of these graphemes, like `[abc]`
+        OneOf(Vec<String>),
+        /// A grapheme made of decimal digits, like `\d`
+        Digit,
+        /// A grapheme made of alphabetic characters
+        Alphabetic,
+        /// A grapheme made of whitespace, like `\s`
+        Whitespace,
+        /// Any grapheme, like `.`
+        Any,
+    }
+
+    impl GraphemeClass {
+        /// Returns whether `grapheme` belongs to the class
+        pub fn matches(&self, grapheme : &str) -> bool {
+            let all = |predicate : fn(char) -> bool| !grapheme.is_empty() && grapheme.chars().all(predicate);
+            match self {
+                GraphemeClass::Literal(literal) => literal == grapheme,
+                GraphemeClass::OneOf(graphemes) => graphemes.iter().any(|member| member == grapheme),
+                GraphemeClass::Digit => all(|c| c.is_ascii_digit()),
+                GraphemeClass::Alphabetic => all(char::is_alphabetic),
+                GraphemeClass::Whitespace => all(char::is_whitespace),
+                GraphemeClass::Any => true,
+            }
+        }
+    }
+
+    /// Calculates the levenshtein distance between a word and a pattern whose positions are grapheme classes.
+    /// Substituting a grapheme of the word for a class containing it costs nothing.
+    ///
+    /// # Arguments
+    /// * `query` - Graphemes to compare with `pattern`
+    /// * `pattern` - Classes the graphemes of `query` should belong to, in order
+    /// * `sub_cost` - Cost of substituting a grapheme for a class not containing it
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::{levenshtein_pattern, GraphemeClass};
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let year = vec![GraphemeClass::Digit; 4];
+    /// assert_eq!(levenshtein_pattern(&Graphemes::from("2024"), &year, 1), 0);
+    /// assert_eq!(levenshtein_pattern(&Graphemes::from("2o24"), &year, 1), 1);
+    /// ```
+    pub fn levenshtein_pattern(query : &Graphemes, pattern : &[GraphemeClass], sub_cost : usize) -> usize {
+        let num_cols = pattern.len() + 1;
+        // previous row of the recurrence matrix, query → row, pattern → column
+        let mut previous_row : Vec<usize> = (0..num_cols).collect();
+        for (row, grapheme) in query.iter().enumerate() {
+            let mut current_row = vec![row + 1; num_cols];
+            for (col, class) in pattern.iter().enumerate() {
+                current_row[col+1] = min(min(
+                    previous_row[col+1] + 1,
+                    current_row[col] + 1
+                ), previous_row[col] + if class.matches(grapheme) {0} else {sub_cost});
+            }
+            previous_row = current_row;
+        }
+        previous_row[num_cols-1]
+    }
+
+    /// A sequence of target graphemes, each either required or optional, compiled for `flex_distance`
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct FlexPattern {
+        /// (grapheme, whether it is optional)
+        graphemes : Vec<(String, bool)>,
+    }
+
+    impl FlexPattern {
+        pub fn new() -> FlexPattern {
+            FlexPattern {
+                graphemes: vec![]
+            }
+        }
+
+        /// Compiles a template where graphemes inside parentheses are optional, e.g. "colo(u)r".
+        /// Panics if the parentheses are nested or unbalanced.
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::metrics::FlexPattern;
+        /// assert_eq!(FlexPattern::from("colo(u)r").len(), 6);
+        /// ```
+        pub fn from(template : &str) -> FlexPattern {
+            let mut pattern = FlexPattern::new();
+            let mut optional = false;
+            for grapheme in Graphemes::from(template).iter() {
+                match *grapheme {
+                    "(" if !optional => optional = true,
+                    ")" if optional => optional = false,
+                    "(" | ")" => panic!("unbalanced parentheses in template {:?}", template),
+                    grapheme => pattern.push(grapheme, optional),
+                }
+            }
+            assert!(!optional, "unbalanced parentheses in template {:?}", template);
+            pattern
+        }
+
+        /// Appends a grapheme, which can be a parenthesis
+        pub fn push(&mut self, grapheme : &str, optional : bool) {
+            self.graphemes.push((grapheme.to_string(), optional));
+        }
+
+        pub fn len(&self) -> usize {
+            self.graphemes.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.graphemes.is_empty()
+        }
+    }
+
+    /// Calculates the levenshtein distance between a query and a pattern where skipping an optional
+    /// pattern grapheme is free. Skipping a required grapheme costs 1 like any insertion.
+    ///
+    /// # Arguments
+    /// * `query` - Graphemes to match against `pattern`
+    /// * `pattern` - Target graphemes, some of which are optional
+    /// * `sub_cost` - Cost of substituting a character with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::{flex_distance, FlexPattern};
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let pattern = FlexPattern::from("colo(u)r");
+    /// assert_eq!(flex_distance(&Graphemes::from("color"), &pattern, 1), 0);
+    /// assert_eq!(flex_distance(&Graphemes::from("colour"), &pattern, 1), 0);
+    /// assert_eq!(flex_distance(&Graphemes::from("colr"), &pattern, 1), 1);
+    /// ```
+    pub fn flex_distance(query : &Graphemes, pattern : &FlexPattern, sub_cost : usize) -> usize {
+        let skip_cost = |&(_, optional) : &(String, bool)| if optional {0} else {1};
+        let num_cols = pattern.len() + 1;
+        // previous row of the recurrence matrix, query → row, pattern → column
+        let mut previous_row : Vec<usize> = vec![0; num_cols];
+        for (col, target) in pattern.graphemes.iter().enumerate() {
+            previous_row[col+1] = previous_row[col] + skip_cost(target);
+        }
+        for (row, grapheme) in query.iter().enumerate() {
+            let mut current_row = vec![row + 1; num_cols];
+            for (col, target) in pattern.graphemes.iter().enumerate() {
+                current_row[col+1] = min(min(
+                    previous_row[col+1] + 1,
+                    current_row[col] + skip_cost(target)
+                ), previous_row[col] + if *grapheme == target.0 {0} else {sub_cost});
+            }
+            previous_row = current_row;
+        }
+        previous_row[num_cols-1]
+    }
+
+    /// Calculates the levenshtein distance between two sequences of words after stemming every word,
+    /// so inflections of the same word are treated as equal
+    ///
+    /// # Arguments
+    /// * `words1` - Words to compare with `words2`
+    /// * `words2` - Words to compare with `words1`
+    /// * `stemmer` - Reduces each word to the stem used for comparison
+    /// * `sub_cost` - Cost of substituting a word with another
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::levenshtein_stemmed;
+    /// use nlp::stemmer::SuffixStripStemmer;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let words1 = Graphemes::from("he runs fast");
+    /// let words2 = Graphemes::from("he running fast");
+    /// assert_eq!(levenshtein_stemmed(&words1.split(" "), &words2.split(" "), &SuffixStripStemmer, 1), 0);
+    /// ```
+    pub fn levenshtein_stemmed(words1 : &[Graphemes], words2 : &[Graphemes], stemmer : &dyn Stemmer, sub_cost : usize) -> usize {
+        let stems1 : Vec<Graphemes> = words1.iter().map(|word| stemmer.stem(word)).collect();
+        let stems2 : Vec<Graphemes> = words2.iter().map(|word| stemmer.stem(word)).collect();
+        levenshtein_distance(&stems1, &stems2, sub_cost)
+    }
+
+    /// Calculates the Jaccard index |Q1 ∩ Q2| / |Q1 ∪ Q2| between the sets of q-grams (runs of `q` graphemes) of two words.
+    /// Words too short to contain a q-gram have an empty set; two such words score 1.0 if equal and 0.0 otherwise.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `q` - Number of graphemes in each q-gram, must be positive
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::jaccard_qgram;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// // {ni, ig, gh, ht} and {na, ac, ch, ht} share one of seven bigrams
+    /// assert_eq!(jaccard_qgram(&Graphemes::from("night"), &Graphemes::from("nacht"), 2), 1.0 / 7.0);
+    /// ```
+    pub fn jaccard_qgram(graphemes1 : &Graphemes, graphemes2 : &Graphemes, q : usize) -> f64 {
+        let qgrams1 : HashSet<&[&str]> = qgrams(graphemes1, q).collect();
+        let qgrams2 : HashSet<&[&str]> = qgrams(graphemes2, q).collect();
+        let union_size = qgrams1.union(&qgrams2).count();
+        if union_size == 0 {
+            return if graphemes1 == graphemes2 {1.0} else {0.0};
+        }
+        qgrams1.intersection(&qgrams2).count() as f64 / union_size as f64
+    }
+
+    /// Calculates the cosine similarity between the count vectors of the n-grams (runs of `n` graphemes) of two words.
+    /// Unlike `jaccard_qgram`, repeated n-grams count as many times as they occur.
+    /// Words too short to contain an n-gram have a zero vector; two such words score 1.0 if equal and 0.0 otherwise.
+    ///
+    /// # Arguments
+    /// * `graphemes1` - Graphemes to compare with `graphemes2`
+    /// * `graphemes2` - Graphemes to compare with `graphemes1`
+    /// * `n` - Number of graphemes in each n-gram, must be positive
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::ngram_cosine;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// // {ni, ig, gh, ht} and {na, ac, ch, ht} share one bigram: 1 / (2 * 2)
+    /// assert_eq!(ngram_cosine(&Graphemes::from("night"), &Graphemes::from("nacht"), 2), 0.25);
+    /// ```
+    pub fn ngram_cosine(graphemes1 : &Graphemes, graphemes2 : &Graphemes, n : usize) -> f64 {
+        let count = |graphemes| {
+            let mut counts : HashMap<&[&str], usize> = HashMap::new();
+            for ngram in qgrams(graphemes, n) {
+                *counts.entry(ngram).or_insert(0) += 1;
+            }
+            counts
+        };
+        let (counts1, counts2) = (count(graphemes1), count(graphemes2));
+        if counts1.is_empty() || counts2.is_empty() {
+            return if graphemes1 == graphemes2 {1.0} else {0.0};
+        }
+        let dot_product : usize = counts1.iter()
+            .map(|(ngram, count1)| count1 * counts2.get(ngram).unwrap_or(&0))
+            .sum();
+        let norm = |counts : &HashMap<&[&str], usize>| (counts.values().map(|count| count * count).sum::<usize>() as f64).sqrt();
+        dot_product as f64 / (norm(&counts1) * norm(&counts2))
+    }
+
+    /// Every run of `q` consecutive graphemes, in order and with repeats
+    fn qgrams<'g, 'a>(graphemes : &'g Graphemes<'a>, q : usize) -> impl Iterator<Item = &'g [&'a str]> {
+        assert!(q > 0, "q-grams must contain at least one grapheme");
+        graphemes[..].windows(q)
+    }
+
+    /// Levenshtein distance where each operation's cost depends on the graphemes involved.
+    /// Deleting removes a grapheme of `graphemes1`, inserting adds a grapheme of `graphemes2`.
+    /// `sub_cost` is only called on graphemes which differ.
+    fn weighted_levenshtein_distance<T, I, D, S>(graphemes1 : &T, graphemes2 : &T, ins_cost : I, del_cost : D, sub_cost : S) -> usize
+        where T : Sequence + ?Sized, I : Fn(&T::Item) -> usize, D : Fn(&T::Item) -> usize, S : Fn(&T::Item, &T::Item) -> usize {
+        let num_rows = graphemes1.len() + 1;
+        let num_cols = graphemes2.len() + 1;
+        let mut recurrence_matrix : Vec<Vec<usize>> = vec![vec![0; num_cols]; num_rows];
+        for row in 1..num_rows {
+            recurrence_matrix[row][0] = recurrence_matrix[row-1][0] + del_cost(&graphemes1[row-1]);
+        }
+        for col in 1..num_cols {
+            recurrence_matrix[0][col] = recurrence_matrix[0][col-1] + ins_cost(&graphemes2[col-1]);
+        }
+
+        for (row, col) in (1..num_rows).cartesian_product(1..num_cols) {
+            let grapheme1 = &graphemes1[row-1];
+            let grapheme2 = &graphemes2[col-1];
+            recurrence_matrix[row][col] = min(min(
+                recurrence_matrix[row-1][col] + del_cost(grapheme1),
+                recurrence_matrix[row][col-1] + ins_cost(grapheme2)
+            ), recurrence_matrix[row-1][col-1] + if grapheme1 == grapheme2 {0} else {sub_cost(grapheme1, grapheme2)})
+        }
+        recurrence_matrix[num_rows-1][num_cols-1]
+    }
+
+    /// Calculates the word error rate (word insertions + deletions + substitutions) / (length of the correct sentence).
+    /// An empty correct sentence has no words, so it gives 0.0 against an empty prediction and infinity otherwise.
+    ///
+    /// # Arguments
+    /// * `actual_sentence` - actual sentence
+    /// * `predict_sentence` - predicted sentence
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::word_error_rate;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// use nlp::max_match;
+    /// use std::collections::HashSet;
+    /// let mut dictionary : HashSet<Graphemes> = HashSet::new();
+    /// dictionary.insert(Graphemes::from("we"));
+    /// dictionary.insert(Graphemes::from("canon"));
+    /// dictionary.insert(Graphemes::from("see"));
+    /// dictionary.insert(Graphemes::from("ash"));
+    /// dictionary.insert(Graphemes::from("ort"));
+    /// dictionary.insert(Graphemes::from("distance"));
+    /// dictionary.insert(Graphemes::from("ahead"));
+    /// let predicted_sentence = max_match(&Graphemes::from("wecanonlyseeashortdistanceahead"), &dictionary);
+    /// let actual_sentence = Graphemes::from("we can only see a short distance ahead");
+    /// assert_eq!(word_error_rate(&actual_sentence, &predicted_sentence),0.625);
+    /// ```
+    pub fn word_error_rate(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> f64 {
+        if actual_sentence.is_empty() {
+            return empty_reference_rate(predict_sentence.is_empty());
+        }
+        let actual_split_sentence = actual_sentence.split(" ");
+        let lev_distance = levenshtein_distance(&actual_split_sentence, &predict_sentence.split(" "), 1);
+        lev_distance as f64 / actual_split_sentence.len() as f64
+    }
+
+    /// Error rate against an empty reference: nothing to get wrong if the prediction is empty too,
+    /// and an unbounded rate otherwise
+    fn empty_reference_rate(predict_is_empty : bool) -> f64 {
+        if predict_is_empty {0.0} else {f64::INFINITY}
+    }
+
+    /// Word error counts of a predicted sentence against the actual sentence
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct WerReport {
+        /// Actual words replaced by another word
+        pub substitutions : usize,
+        /// Predicted words missing from the actual sentence
+        pub insertions : usize,
+        /// Actual words missing from the predicted sentence
+        pub deletions : usize,
+        /// Number of words in the actual sentence
+        pub reference_words : usize,
+        /// (substitutions + insertions + deletions) / reference_words, as given by `word_error_rate`
+        pub wer : f64,
+    }
+
+    /// Calculates the word error rate like `word_error_rate` along with the number of each kind of error,
+    /// counted on the word alignment of the two sentences
+    ///
+    /// # Arguments
+    /// * `actual_sentence` - actual sentence
+    /// * `predict_sentence` - predicted sentence
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::word_error_rate_detailed;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let report = word_error_rate_detailed(&Graphemes::from("we can see it"), &Graphemes::from("we see it clearly"));
+    /// assert_eq!((report.substitutions, report.insertions, report.deletions, report.reference_words), (0, 1, 1, 4));
+    /// assert_eq!(report.wer, 0.5);
+    /// ```
+    pub fn word_error_rate_detailed(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> WerReport {
+        let actual_words = actual_sentence.split(" ");
+        let predict_words = predict_sentence.split(" ");
+        let path = alignment_path(&actual_words, &predict_words, 1);
+        let steps = alignment_steps(&actual_words, &predict_words, &path);
+        let count = |kind : AlignmentStep| steps.iter().filter(|&&step| step == kind).count();
+        let (substitutions, insertions, deletions) = (count(AlignmentStep::Substitute), count(AlignmentStep::Insert), count(AlignmentStep::Delete));
+        WerReport {
+            substitutions,
+            insertions,
+            deletions,
+            reference_words: actual_words.len(),
+            wer: if actual_sentence.is_empty() {
+                empty_reference_rate(predict_sentence.is_empty())
+            } else {
+                (substitutions + insertions + deletions) as f64 / actual_words.len() as f64
+            },
+        }
+    }
+
+    /// Calculates the word error rate like `word_error_rate`, splitting both sentences into words with `tokenizer`.
+    /// A correct sentence without tokens gives 0.0 against a prediction without tokens and infinity otherwise.
+    ///
+    /// # Arguments
+    /// * `actual_sentence` - actual sentence
+    /// * `predict_sentence` - predicted sentence
+    /// * `tokenizer` - Splits each sentence into the words to compare
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::word_error_rate_with;
+    /// use nlp::tokenizer::{WhitespaceTokenizer, UnicodeWordTokenizer};
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let actual_sentence = Graphemes::from("hello world");
+    /// let predicted_sentence = Graphemes::from("hello, world!");
+    /// assert_eq!(word_error_rate_with(&actual_sentence, &predicted_sentence, &WhitespaceTokenizer), 1.0);
+    /// assert_eq!(word_error_rate_with(&actual_sentence, &predicted_sentence, &UnicodeWordTokenizer), 0.0);
+    /// ```
+    pub fn word_error_rate_with(actual_sentence : &Graphemes, predict_sentence : &Graphemes, tokenizer : &dyn Tokenizer) -> f64 {
+        let actual_tokens = tokenizer.tokenize(actual_sentence);
+        let predict_tokens = tokenizer.tokenize(predict_sentence);
+        if actual_tokens.is_empty() {
+            return empty_reference_rate(predict_tokens.is_empty());
+        }
+        let lev_distance = levenshtein_distance(&actual_tokens, &predict_tokens, 1);
+        lev_distance as f64 / actual_tokens.len() as f64
+    }
 
-    /// Calculates the levenshtein distance between two words
+    /// Calculates the word error rate of a predicted sentence against each of several acceptable references,
+    /// returning the lowest rate and the index of the reference achieving it. Ties go to the first reference.
+    /// Panics if `references` is empty.
     ///
     /// # Arguments
-    /// * `graphemes1` - Graphemes to compare with `graphemes2`
-    /// * `graphemes2` - Graphemes to compare with `graphemes1`
-    /// * `sub_cost` - Cost of substituting a character with another
+    /// * `references` - Acceptable actual sentences
+    /// * `predict_sentence` - predicted sentence
     ///
     /// # Example
     /// ```
-    /// use nlp::metrics::levenshtein_distance;
+    /// use nlp::metrics::min_wer_over_references;
     /// use nlp::graphemes_struct::Graphemes;
-    /// assert_eq!(levenshtein_distance(&Graphemes::from("book"), &Graphemes::from("back"), 1), 2);
-    /// assert_eq!(levenshtein_distance(&Graphemes::from("back"), &Graphemes::from("book"), 1), 2);
-    /// assert_eq!(levenshtein_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1), 3);
+    /// let references = vec![Graphemes::from("the cat sat"), Graphemes::from("a cat sat down")];
+    /// assert_eq!(min_wer_over_references(&references, &Graphemes::from("a cat sat")), (0.25, 1));
     /// ```
-    pub fn levenshtein_distance<'a, T, U>(graphemes1 : &T, graphemes2: &T, sub_cost : usize) -> usize
-        where T : Len + Index<usize, Output = U>, U: PartialEq + 'a {
-        levenshtein_distance_recurrence_matrix(graphemes1, graphemes2, sub_cost)[graphemes1.len()][graphemes2.len()]
+    pub fn min_wer_over_references(references : &[Graphemes], predict_sentence : &Graphemes) -> (f64, usize) {
+        assert!(!references.is_empty(), "references must not be empty");
+        references.iter()
+            .map(|reference| word_error_rate(reference, predict_sentence))
+            .enumerate()
+            .fold((f64::INFINITY, 0), |(best_rate, best_index), (index, rate)| {
+                if rate < best_rate { (rate, index) } else { (best_rate, best_index) }
+            })
     }
 
-
-    fn levenshtein_distance_recurrence_matrix<'a, T, U>(graphemes1 : &T, graphemes2 : &T, sub_cost : usize) -> Vec<Vec<usize>>
-        where T : Len + Index<usize, Output = U>, U : PartialEq + 'a {
-        let num_rows = graphemes1.len() + 1;
-        let num_cols = graphemes2.len() + 1;
-        let mut recurrence_matrix : Vec<Vec<usize>> = vec![vec![0; num_cols]; num_rows];
-        // graphemes1 → row
-        // graphemes2 → column
-        for row in 1..num_rows {
-            recurrence_matrix[row][0] = row;
+    /// Calculates the character error rate (grapheme insertions + deletions + substitutions) / (graphemes of the correct sentence).
+    /// Graphemes are compared, so a letter with a combining mark is one character, and spaces count like any other grapheme.
+    /// An empty correct sentence gives 0.0 against an empty prediction and infinity otherwise.
+    ///
+    /// # Arguments
+    /// * `actual_sentence` - actual sentence
+    /// * `predict_sentence` - predicted sentence
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::character_error_rate;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// assert_eq!(character_error_rate(&Graphemes::from("kitten"), &Graphemes::from("sitten")), 1.0 / 6.0);
+    /// assert_eq!(character_error_rate(&Graphemes::from("cafe\u{301}"), &Graphemes::from("cafe")), 0.25);
+    /// ```
+    pub fn character_error_rate(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> f64 {
+        if actual_sentence.is_empty() {
+            return empty_reference_rate(predict_sentence.is_empty());
         }
-        for col in 1..num_cols {
-            recurrence_matrix[0][col] = col;
+        levenshtein_distance(actual_sentence, predict_sentence, 1) as f64 / actual_sentence.len() as f64
+    }
+
+    /// Calculates the word error rate and the character error rate of a predicted sentence at once,
+    /// returning `(word error rate, character error rate)`. Both sentences are segmented into graphemes once
+    /// and the words are split from those graphemes. The character error rate is the grapheme levenshtein distance,
+    /// spaces included, divided by the number of graphemes of the actual sentence.
+    ///
+    /// # Arguments
+    /// * `actual_sentence` - actual sentence
+    /// * `predict_sentence` - predicted sentence
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::dual_error_rates;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let (wer, cer) = dual_error_rates(&Graphemes::from("a short distance"), &Graphemes::from("a shorter distance"));
+    /// assert_eq!((wer, cer), (1.0 / 3.0, 2.0 / 16.0));
+    /// ```
+    pub fn dual_error_rates(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> (f64, f64) {
+        if actual_sentence.is_empty() {
+            let rate = empty_reference_rate(predict_sentence.is_empty());
+            return (rate, rate);
         }
+        let actual_split_sentence = actual_sentence.split(" ");
+        let word_distance = levenshtein_distance(&actual_split_sentence, &predict_sentence.split(" "), 1);
+        (word_distance as f64 / actual_split_sentence.len() as f64, character_error_rate(actual_sentence, predict_sentence))
+    }
 
-        for (row, col) in (1..num_rows).cartesian_product(1..num_cols) {
-            recurrence_matrix[row][col] = min(min(
-                recurrence_matrix[row-1][col]+1,
-                recurrence_matrix[row][col-1]+1
-            ),  recurrence_matrix[row-1][col-1] + if graphemes1[row-1] == graphemes2[col-1] {0} else {sub_cost})
+    /// Estimates the corpus word error rate with a bootstrap confidence interval, returning
+    /// `(point estimate, lower bound, upper bound)`. The corpus WER is the total number of word errors
+    /// divided by the total number of reference words. Each of the `iterations` resamples draws
+    /// sentence pairs with replacement using a random number generator seeded with `seed`.
+    /// A corpus without reference words, e.g. empty `pairs`, has a rate of 0.0 if there are no word errors
+    /// either, and `f64::INFINITY` otherwise.
+    ///
+    /// # Panics
+    /// Panics if `confidence` is not strictly between 0 and 1.
+    ///
+    /// # Arguments
+    /// * `pairs` - (actual sentence, predicted sentence) pairs
+    /// * `iterations` - Number of bootstrap resamples
+    /// * `confidence` - Confidence level of the interval, e.g. 0.95
+    /// * `seed` - Seed making the resampling reproducible
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::wer_bootstrap_ci;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// let pairs = vec![
+    ///     (Graphemes::from("we can see"), Graphemes::from("we can see")),
+    ///     (Graphemes::from("a short distance"), Graphemes::from("a shorter distance")),
+    /// ];
+    /// let (wer, lower, upper) = wer_bootstrap_ci(&pairs, 1000, 0.95, 42);
+    /// assert_eq!(wer, 1.0 / 6.0);
+    /// assert!(lower <= wer && wer <= upper);
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn wer_bootstrap_ci(pairs : &[(Graphemes, Graphemes)], iterations : usize, confidence : f64, seed : u64) -> (f64, f64, f64) {
+        assert!(confidence > 0.0 && confidence < 1.0, "confidence must be between 0 and 1");
+        // (word errors, reference words) of each pair
+        let counts : Vec<(usize, usize)> = pairs.iter().map(|(actual_sentence, predict_sentence)| {
+            let actual_split_sentence = actual_sentence.split(" ");
+            (levenshtein_distance(&actual_split_sentence, &predict_sentence.split(" "), 1), actual_split_sentence.len())
+        }).collect();
+        let corpus_wer = |sample : &mut dyn Iterator<Item = &(usize, usize)>| {
+            let (errors, words) = sample.fold((0, 0), |(errors, words), &(pair_errors, pair_words)| (errors + pair_errors, words + pair_words));
+            if words == 0 {
+                return empty_reference_rate(errors == 0);
+            }
+            errors as f64 / words as f64
+        };
+        let point_estimate = corpus_wer(&mut counts.iter());
+        if counts.is_empty() || iterations == 0 {
+            return (point_estimate, point_estimate, point_estimate);
         }
-        recurrence_matrix
+
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut estimates : Vec<f64> = (0..iterations).map(|_| {
+            let sample : Vec<&(usize, usize)> = (0..counts.len()).map(|_| &counts[rng.gen_range(0..counts.len())]).collect();
+            corpus_wer(&mut sample.into_iter())
+        }).collect();
+        estimates.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let tail = (1.0 - confidence) / 2.0;
+        let percentile = |p : f64| estimates[((p * iterations as f64) as usize).min(iterations - 1)];
+        (point_estimate, percentile(tail), percentile(1.0 - tail))
     }
 
-    /// Calculates the word error rate (word insertions + deletions + substitutions) / (length of the correct sentence)
+    /// Calculates a word edit distance where moving a contiguous block of words to another position costs 1,
+    /// like the shifts of translation edit rate. Shifts are chosen greedily: while moving some block of predicted
+    /// words that also occurs in the actual sentence lowers the levenshtein distance by more than 1, the move
+    /// lowering it the most is applied. The result is the number of moves plus the remaining word levenshtein distance,
+    /// so it is never more than the word level levenshtein distance used by `word_error_rate`.
     ///
     /// # Arguments
     /// * `actual_sentence` - actual sentence
@@ -63,26 +1275,47 @@ mod metrics {
     ///
     /// # Example
     /// ```
-    /// use nlp::metrics::word_error_rate;
+    /// use nlp::metrics::word_move_distance;
     /// use nlp::graphemes_struct::Graphemes;
-    /// use nlp::max_match;
-    /// use std::collections::HashSet;
-    /// let mut dictionary : HashSet<Graphemes> = HashSet::new();
-    /// dictionary.insert(Graphemes::from("we"));
-    /// dictionary.insert(Graphemes::from("canon"));
-    /// dictionary.insert(Graphemes::from("see"));
-    /// dictionary.insert(Graphemes::from("ash"));
-    /// dictionary.insert(Graphemes::from("ort"));
-    /// dictionary.insert(Graphemes::from("distance"));
-    /// dictionary.insert(Graphemes::from("ahead"));
-    /// let predicted_sentence = max_match(&Graphemes::from("wecanonlyseeashortdistanceahead"), &dictionary);
     /// let actual_sentence = Graphemes::from("we can only see a short distance ahead");
-    /// assert_eq!(word_error_rate(&actual_sentence, &predicted_sentence),0.625);
+    /// let predicted_sentence = Graphemes::from("a short distance ahead we can only see");
+    /// assert_eq!(word_move_distance(&actual_sentence, &predicted_sentence), 1);
     /// ```
-    pub fn word_error_rate(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> f64 {
-        let actual_split_sentence = actual_sentence.split(" ");
-        let lev_distance = levenshtein_distance(&actual_split_sentence, &predict_sentence.split(" "), 1);
-        lev_distance as f64 / actual_split_sentence.len() as f64
+    pub fn word_move_distance(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> usize {
+        let actual_words : Vec<String> = actual_sentence.split(" ").iter().map(|word| word.to_string()).collect();
+        let actual_words : Vec<&str> = actual_words.iter().map(|word| word.as_str()).collect();
+        let predict_words : Vec<String> = predict_sentence.split(" ").iter().map(|word| word.to_string()).collect();
+        let mut predict_words : Vec<&str> = predict_words.iter().map(|word| word.as_str()).collect();
+        let mut moves = 0;
+        let mut distance = levenshtein_distance(&actual_words, &predict_words, 1);
+        loop {
+            let mut best_move : Option<(usize, Vec<&str>)> = None;
+            for start in 0..predict_words.len() {
+                for end in start+1..=predict_words.len() {
+                    let block = &predict_words[start..end];
+                    if !actual_words.windows(block.len()).any(|window| window == block) {
+                        // longer blocks starting here do not occur either
+                        break;
+                    }
+                    let rest : Vec<&str> = predict_words[..start].iter().chain(predict_words[end..].iter()).cloned().collect();
+                    for destination in (0..=rest.len()).filter(|&destination| destination != start) {
+                        let moved : Vec<&str> = rest[..destination].iter().chain(block.iter()).chain(rest[destination..].iter()).cloned().collect();
+                        let moved_distance = levenshtein_distance(&actual_words, &moved, 1);
+                        if moved_distance + 1 < best_move.as_ref().map_or(distance, |(best_distance, _)| *best_distance + 1) {
+                            best_move = Some((moved_distance, moved));
+                        }
+                    }
+                }
+            }
+            match best_move {
+                Some((moved_distance, moved)) => {
+                    moves += 1;
+                    distance = moved_distance;
+                    predict_words = moved;
+                }
+                None => return moves + distance
+            }
+        }
     }
 
     /// Calculates the word accuracy 1 - (word insertions + deletions + substitutions) / (length of the correct sentence)
@@ -112,14 +1345,158 @@ mod metrics {
     pub fn word_accuracy(actual_sentence : &Graphemes, predict_sentence : &Graphemes) -> f64 {
         1.0 - word_error_rate(actual_sentence, predict_sentence)
     }
+
+    /// Calculates the precision, recall and F1 score of the word boundaries of a segmentation.
+    /// Both sentences are segmented with spaces, and every position between two graphemes of the
+    /// unsegmented sentence is scored as a boundary or not. When there are no predicted (gold) boundaries
+    /// the precision (recall) is 1.0. Panics if the sentences differ once their spaces are removed.
+    ///
+    /// # Arguments
+    /// * `gold_sentence` - Correctly segmented sentence
+    /// * `predict_sentence` - Predicted segmentation of the same sentence
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::boundary_prf;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// // 2 of the 3 predicted boundaries are among the 3 gold boundaries
+    /// let (precision, recall, f1) = boundary_prf(&Graphemes::from("他 特别 喜欢 北京烤鸭"), &Graphemes::from("他 特别 喜 欢北京烤鸭"));
+    /// assert_eq!((precision, recall, f1), (2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0));
+    /// ```
+    pub fn boundary_prf(gold_sentence : &Graphemes, predict_sentence : &Graphemes) -> (f64, f64, f64) {
+        let gold_boundaries = boundaries(gold_sentence);
+        let predict_boundaries = boundaries(predict_sentence);
+        assert!(gold_sentence.iter().filter(|&&grapheme| grapheme != " ").eq(predict_sentence.iter().filter(|&&grapheme| grapheme != " ")),
+                "both segmentations must be of the same sentence");
+        let correct = gold_boundaries.iter().zip(predict_boundaries.iter()).filter(|&(&gold, &predict)| gold && predict).count();
+        let ratio = |count : usize, total : usize| if total == 0 {1.0} else {count as f64 / total as f64};
+        let precision = ratio(correct, predict_boundaries.iter().filter(|&&boundary| boundary).count());
+        let recall = ratio(correct, gold_boundaries.iter().filter(|&&boundary| boundary).count());
+        let f1 = if precision + recall == 0.0 {0.0} else {2.0 * precision * recall / (precision + recall)};
+        (precision, recall, f1)
+    }
+
+    /// Calculates the fraction of positions between two graphemes where two segmentations of the same sentence agree
+    /// on whether there is a word boundary, like `boundary_prf` but symmetric, for comparing two systems or annotators.
+    /// A sentence with fewer than two graphemes has no positions and full agreement.
+    /// Panics if the segmentations differ once their spaces are removed.
+    ///
+    /// # Arguments
+    /// * `segmentation1` - Segmentation of a sentence with spaces
+    /// * `segmentation2` - Other segmentation of the same sentence
+    ///
+    /// # Example
+    /// ```
+    /// use nlp::metrics::segmentation_agreement;
+    /// use nlp::graphemes_struct::Graphemes;
+    /// // the segmentations only disagree between "北京" and "烤鸭"
+    /// assert_eq!(segmentation_agreement(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京 烤鸭")), 2.0 / 3.0);
+    /// ```
+    pub fn segmentation_agreement(segmentation1 : &Graphemes, segmentation2 : &Graphemes) -> f64 {
+        assert!(segmentation1.iter().filter(|&&grapheme| grapheme != " ").eq(segmentation2.iter().filter(|&&grapheme| grapheme != " ")),
+                "both segmentations must be of the same sentence");
+        let (boundaries1, boundaries2) = (boundaries(segmentation1), boundaries(segmentation2));
+        if boundaries1.is_empty() {
+            return 1.0;
+        }
+        let agreements = boundaries1.iter().zip(boundaries2.iter()).filter(|&(boundary1, boundary2)| boundary1 == boundary2).count();
+        agreements as f64 / boundaries1.len() as f64
+    }
+
+    /// Whether a space separates each pair of consecutive non-space graphemes
+    fn boundaries(sentence : &Graphemes) -> Vec<bool> {
+        let mut boundaries = vec![];
+        let mut after_space = false;
+        let mut seen_grapheme = false;
+        for &grapheme in sentence.iter() {
+            if grapheme == " " {
+                after_space = true;
+                continue;
+            }
+            if seen_grapheme {
+                boundaries.push(after_space);
+            }
+            seen_grapheme = true;
+            after_space = false;
+        }
+        boundaries
+    }
+
+    /// Counts of reference words substituted by predicted words, accumulated over word-level alignments
+    #[derive(Debug, Clone, Default)]
+    pub struct ConfusionMatrix {
+        /// (actual word, predicted word) → number of substitutions
+        counts : HashMap<(String, String), usize>,
+    }
+
+    impl ConfusionMatrix {
+        pub fn new() -> ConfusionMatrix {
+            ConfusionMatrix {
+                counts: HashMap::new()
+            }
+        }
+
+        /// Aligns the words of two sentences split on spaces, like `word_error_rate`,
+        /// and counts every substituted pair of words
+        pub fn add(&mut self, actual_sentence : &Graphemes, predict_sentence : &Graphemes) {
+            let actual_words = actual_sentence.split(" ");
+            let predict_words = predict_sentence.split(" ");
+            let path = alignment_path(&actual_words, &predict_words, 1);
+            for (step, coords) in alignment_steps(&actual_words, &predict_words, &path).into_iter().zip(path.windows(2)) {
+                if step == AlignmentStep::Substitute {
+                    let (row, col) = coords[1];
+                    let confusion = (actual_words[row-1].to_string(), predict_words[col-1].to_string());
+                    *self.counts.entry(confusion).or_insert(0) += 1;
+                }
+            }
+        }
+
+        /// Number of times `actual_word` was substituted by `predict_word`
+        pub fn count(&self, actual_word : &str, predict_word : &str) -> usize {
+            *self.counts.get(&(actual_word.to_string(), predict_word.to_string())).unwrap_or(&0)
+        }
+
+        /// Returns the `n` most frequent confusions as `(actual word, predicted word, count)`,
+        /// sorted by decreasing count and then by words
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::metrics::ConfusionMatrix;
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let mut confusions = ConfusionMatrix::new();
+        /// confusions.add(&Graphemes::from("我 在 北京"), &Graphemes::from("我 再 北京"));
+        /// confusions.add(&Graphemes::from("在 家"), &Graphemes::from("再 家"));
+        /// assert_eq!(confusions.most_frequent(1), vec![(String::from("在"), String::from("再"), 2)]);
+        /// ```
+        pub fn most_frequent(&self, n : usize) -> Vec<(String, String, usize)> {
+            let mut confusions : Vec<(String, String, usize)> = self.counts.iter()
+                .map(|((actual_word, predict_word), &count)| (actual_word.clone(), predict_word.clone(), count))
+                .collect();
+            confusions.sort_by(|confusion1, confusion2| confusion2.2.cmp(&confusion1.2)
+                .then_with(|| (&confusion1.0, &confusion1.1).cmp(&(&confusion2.0, &confusion2.1))));
+            confusions.truncate(n);
+            confusions
+        }
+    }
 }
 
-#[cfg(tests)]
+#[cfg(test)]
 mod test_cases {
-    use crate::metrics::{levenshtein_distance, word_error_rate};
+    use crate::metrics::{levenshtein_distance, cost_matrix, levenshtein_distance_linear, within_distance, levenshtein_distance_bounded,
+                         levenshtein_ratio, jaro_similarity, jaro_winkler_similarity, block_edit_distance, levenshtein_display_width, keyboard_levenshtein,
+                         levenshtein_free_delete, word_error_rate, word_error_rate_with, word_error_rate_detailed, word_move_distance,
+                         min_wer_over_references, dual_error_rates, character_error_rate};
+    use crate::tokenizer::{Tokenizer, WhitespaceTokenizer, CharTokenizer, UnicodeWordTokenizer};
+    use crate::stemmer::SuffixStripStemmer;
+    use crate::metrics::{levenshtein_stemmed, jaccard_qgram, ngram_cosine, analyze, DistanceResult, common_prefix_len, common_suffix_len,
+                         circular_levenshtein, levenshtein_pattern, GraphemeClass, FlexPattern, flex_distance, boundary_prf,
+                         ConfusionMatrix, DistanceCache, typo_likelihood, TypoClass, segmentation_agreement};
     use crate::graphemes_struct::Graphemes;
     use crate::max_match;
     use std::collections::HashSet;
+    use std::cell::Cell;
+    use std::ops::Index;
+    use len_trait::len::{Len, Empty};
 
     #[test]
     fn edit_distance_basic_test() {
@@ -170,6 +1547,201 @@ mod test_cases {
         assert_eq!(levenshtein_distance(&Graphemes::from("不患人之不己知患不知人也"), &Graphemes::from("己所不欲勿施于人"), 1), 10);
     }
 
+    #[test]
+    fn levenshtein_distance_linear_test() {
+        let words = ["book", "back", "kitten", "sitting", "", "己所不欲勿施于人", "不患人之不己知患不知人也", "北京"];
+        for word1 in words.iter() {
+            for word2 in words.iter() {
+                let (graphemes1, graphemes2) = (Graphemes::from(word1), Graphemes::from(word2));
+                for &sub_cost in [0, 1, 2].iter() {
+                    assert_eq!(levenshtein_distance_linear(&graphemes1, &graphemes2, sub_cost),
+                               levenshtein_distance(&graphemes1, &graphemes2, sub_cost));
+                }
+            }
+        }
+        assert_eq!(levenshtein_distance_linear(&Graphemes::from(""), &Graphemes::from(""), 1), 0);
+        assert_eq!(levenshtein_distance_linear(&Graphemes::from("己所不欲勿施于人"), &Graphemes::from("不患人之不己知患不知人也"), 1), 10);
+    }
+
+    #[test]
+    fn cost_matrix_test() {
+        let matrix = cost_matrix(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京"), 1);
+        assert_eq!(matrix, vec![
+            vec![0, 1, 2],
+            vec![1, 0, 1],
+            vec![2, 1, 0],
+            vec![3, 2, 1],
+            vec![4, 3, 2],
+        ]);
+        assert_eq!(cost_matrix(&Graphemes::from(""), &Graphemes::from(""), 1), vec![vec![0]]);
+        assert_eq!(cost_matrix(&Graphemes::from("ab"), &Graphemes::from(""), 1), vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(cost_matrix(&Graphemes::from("a"), &Graphemes::from("b"), 2)[1][1], 2);
+    }
+
+    #[test]
+    fn levenshtein_distance_bounded_test() {
+        let (kitten, sitting) = (Graphemes::from("kitten"), Graphemes::from("sitting"));
+        assert_eq!(levenshtein_distance_bounded(&kitten, &sitting, 1, 2), None);
+        assert_eq!(levenshtein_distance_bounded(&kitten, &sitting, 1, 5), Some(3));
+        assert_eq!(levenshtein_distance_bounded(&kitten, &sitting, 1, 3), Some(3));
+        assert_eq!(levenshtein_distance_bounded(&Graphemes::from(""), &Graphemes::from(""), 1, 0), Some(0));
+        let words = ["kitten", "sitting", "北京烤鸭", "北京", "", "receive", "recieve"];
+        for word1 in words.iter() {
+            for word2 in words.iter() {
+                let (graphemes1, graphemes2) = (Graphemes::from(word1), Graphemes::from(word2));
+                for &sub_cost in [1, 2].iter() {
+                    let distance = levenshtein_distance(&graphemes1, &graphemes2, sub_cost);
+                    for max in 0..8 {
+                        let expected = if distance <= max {Some(distance)} else {None};
+                        assert_eq!(levenshtein_distance_bounded(&graphemes1, &graphemes2, sub_cost, max), expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn levenshtein_distance_bounded_abort_test() {
+        // a length difference beyond the bound is rejected before any grapheme is read
+        let (graphemes1, graphemes2) = (counting("kitten"), counting("sitting in the sun"));
+        assert_eq!(levenshtein_distance_bounded(&graphemes1, &graphemes2, 1, 2), None);
+        assert_eq!(graphemes1.lookups.get() + graphemes2.lookups.get(), 0);
+        // the first row already exceeds the bound
+        let (graphemes1, graphemes2) = (counting("abcdefgh"), counting("stuvwxyz"));
+        assert_eq!(levenshtein_distance_bounded(&graphemes1, &graphemes2, 2, 0), None);
+        assert_eq!(graphemes1.lookups.get(), 8);
+    }
+
+    /// Graphemes counting how often they are indexed
+    struct CountingGraphemes<'a> {
+        graphemes : Graphemes<'a>,
+        lookups : Cell<usize>,
+    }
+
+    impl<'a> Empty for CountingGraphemes<'a> {
+        fn is_empty(&self) -> bool {
+            self.graphemes.is_empty()
+        }
+    }
+
+    impl<'a> Len for CountingGraphemes<'a> {
+        fn len(&self) -> usize {
+            self.graphemes.len()
+        }
+    }
+
+    impl<'a> Index<usize> for CountingGraphemes<'a> {
+        type Output = &'a str;
+
+        fn index(&self, index : usize) -> &&'a str {
+            self.lookups.set(self.lookups.get() + 1);
+            &self.graphemes[index]
+        }
+    }
+
+    fn counting(word : &str) -> CountingGraphemes<'_> {
+        CountingGraphemes { graphemes: Graphemes::from(word), lookups: Cell::new(0) }
+    }
+
+    #[test]
+    fn within_distance_test() {
+        let words = ["kitten", "sitting", "北京烤鸭", "北京", "", "receive", "recieve"];
+        for word1 in words.iter() {
+            for word2 in words.iter() {
+                let (graphemes1, graphemes2) = (Graphemes::from(word1), Graphemes::from(word2));
+                for &sub_cost in [1, 2].iter() {
+                    let distance = levenshtein_distance(&graphemes1, &graphemes2, sub_cost);
+                    for k in 0..8 {
+                        assert_eq!(within_distance(&graphemes1, &graphemes2, sub_cost, k), distance <= k);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn within_distance_early_bailout_test() {
+        let (word1, word2) = ("abcdefghijklmnopqrst", "zyxwvutsrqponmlkjihg");
+        let full = (counting(word1), counting(word2));
+        assert_eq!(levenshtein_distance(&full.0, &full.1, 1), 20);
+        let full_lookups = full.0.lookups.get() + full.1.lookups.get();
+        // no row is needed when the lengths differ too much
+        let short = (counting(word1), counting("ab"));
+        assert!(!within_distance(&short.0, &short.1, 1, 3));
+        assert_eq!(short.0.lookups.get() + short.1.lookups.get(), 0);
+        // every cell of the third row exceeds 2
+        let different = (counting(word1), counting(word2));
+        assert!(!within_distance(&different.0, &different.1, 1, 2));
+        assert_eq!(different.0.lookups.get(), 3 * word2.len());
+        assert!(different.0.lookups.get() + different.1.lookups.get() < full_lookups);
+        // the end is within reach after the first row by substituting every remaining grapheme
+        let similar = (counting("abcdefghijklmnopqrst"), counting("abcdefghijklmnopqrsx"));
+        assert!(within_distance(&similar.0, &similar.1, 1, 19));
+        assert_eq!(similar.0.lookups.get(), word2.len());
+        assert!(within_distance(&similar.0, &similar.1, 1, 1));
+        assert!(similar.0.lookups.get() + similar.1.lookups.get() <= full_lookups);
+    }
+
+    #[test]
+    fn block_edit_distance_test() {
+        assert_eq!(block_edit_distance(&Graphemes::from(""), &Graphemes::from(""), 1), 0);
+        assert_eq!(block_edit_distance(&Graphemes::from("same"), &Graphemes::from("same"), 1), 0);
+        // a single 10 grapheme insertion is one block
+        assert_eq!(block_edit_distance(&Graphemes::from("abc"), &Graphemes::from("abXXXXXXXXXXc"), 1), 1);
+        assert_eq!(block_edit_distance(&Graphemes::from("abXXXXXXXXXXc"), &Graphemes::from("abc"), 1), 1);
+        // two separate substitution runs
+        assert_eq!(block_edit_distance(&Graphemes::from("kitten"), &Graphemes::from("sitten"), 1), 1);
+        assert_eq!(block_edit_distance(&Graphemes::from("kitten"), &Graphemes::from("sittin"), 1), 2);
+    }
+
+    #[test]
+    fn keyboard_levenshtein_test() {
+        let distance = |word1, word2| keyboard_levenshtein(&Graphemes::from(word1), &Graphemes::from(word2));
+        // "s" is next to "a" while "z" is diagonally below it
+        assert!(distance("cat", "cst") < distance("cat", "czt"));
+        assert!(distance("cat", "czt") < distance("cat", "cpt"));
+        assert_eq!(distance("cat", "cst"), 0.5);
+        assert_eq!(distance("cat", "cpt"), 1.0);
+        assert_eq!(distance("cat", "cat"), 0.0);
+        assert_eq!(distance("", "cat"), 3.0);
+        assert_eq!(distance("cat", "act"), 2.0);
+        // graphemes off the keyboard always cost 1
+        assert_eq!(distance("cat", "cAt"), 1.0);
+        assert_eq!(distance("北京", "北鸭"), 1.0);
+        assert_eq!(distance("kitten", "sitting") as usize, levenshtein_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1));
+    }
+
+    #[test]
+    fn levenshtein_free_delete_test() {
+        let free_delete : HashSet<&str> = ["-", "'", " "].iter().cloned().collect();
+        let distance = |word1, word2, sub_cost| levenshtein_free_delete(&Graphemes::from(word1), &Graphemes::from(word2), sub_cost, &free_delete);
+        assert_eq!(distance("e-mail", "email", 1), 0);
+        assert_eq!(distance("don't", "dont", 1), 0);
+        assert_eq!(distance("北京 烤鸭", "北京烤鸭", 1), 0);
+        // only deletions from the first word are free
+        assert_eq!(distance("email", "e-mail", 1), 1);
+        assert_eq!(distance("e-mail", "e_mail", 1), 1);
+        assert_eq!(distance("e-mail", "e_mail", 2), 1);
+        // deleting the hyphen is cheaper than substituting it
+        assert_eq!(distance("-", "a", 1), 1);
+        assert_eq!(distance("kitten", "sitting", 1), levenshtein_distance(&Graphemes::from("kitten"), &Graphemes::from("sitting"), 1));
+        assert_eq!(levenshtein_free_delete(&Graphemes::from("e-mail"), &Graphemes::from("email"), 1, &HashSet::new()), 1);
+    }
+
+    #[test]
+    fn levenshtein_display_width_test() {
+        assert_eq!(levenshtein_display_width(&Graphemes::from(""), &Graphemes::from("")), 0);
+        // deleting a CJK grapheme costs 2
+        assert_eq!(levenshtein_display_width(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京鸭")), 2);
+        assert_eq!(levenshtein_display_width(&Graphemes::from("a"), &Graphemes::from("")), 1);
+        assert_eq!(levenshtein_display_width(&Graphemes::from(""), &Graphemes::from("己所不欲")), 8);
+        // substitution costs the width of the wider grapheme
+        assert_eq!(levenshtein_display_width(&Graphemes::from("a"), &Graphemes::from("他")), 2);
+        assert_eq!(levenshtein_display_width(&Graphemes::from("book"), &Graphemes::from("back")), 2);
+        // combining marks stay within a single narrow grapheme
+        assert_eq!(levenshtein_display_width(&Graphemes::from("e\u{301}"), &Graphemes::from("")), 1);
+    }
+
     #[test]
     fn word_error_rate_test() {
         let mut dictionary : HashSet<Graphemes> = HashSet::new();
@@ -185,4 +1757,447 @@ mod test_cases {
         assert_eq!(word_error_rate(&actual_sentence, &predicted_sentence),0.625);
         assert_eq!(word_error_rate(&actual_sentence, &actual_sentence),0.0)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn word_error_rate_empty_reference_test() {
+        let empty = Graphemes::from("");
+        assert_eq!(word_error_rate(&empty, &empty), 0.0);
+        assert_eq!(word_error_rate(&empty, &Graphemes::from("we see")), f64::INFINITY);
+        assert_eq!(word_error_rate(&Graphemes::from("we see"), &empty), 1.0);
+        assert_eq!(word_error_rate_detailed(&empty, &empty).wer, 0.0);
+        assert_eq!(word_error_rate_detailed(&empty, &Graphemes::from("we see")).wer, f64::INFINITY);
+        assert_eq!(dual_error_rates(&empty, &empty), (0.0, 0.0));
+        assert_eq!(dual_error_rates(&empty, &Graphemes::from("we")), (f64::INFINITY, f64::INFINITY));
+        // only whitespace has no tokens
+        assert_eq!(word_error_rate_with(&Graphemes::from("  "), &empty, &WhitespaceTokenizer), 0.0);
+        assert_eq!(word_error_rate_with(&Graphemes::from("  "), &Graphemes::from("we"), &WhitespaceTokenizer), f64::INFINITY);
+    }
+
+    #[test]
+    fn character_error_rate_test() {
+        // "a̐" substituted with "a" and "!" inserted
+        let actual = Graphemes::from("北京a̐é");
+        let predicted = Graphemes::from("北京aé!");
+        assert_eq!(actual.len(), 4);
+        assert_eq!(character_error_rate(&actual, &predicted), 0.5);
+        assert_eq!(character_error_rate(&actual, &actual), 0.0);
+        assert_eq!(character_error_rate(&Graphemes::from(""), &Graphemes::from("")), 0.0);
+        assert_eq!(character_error_rate(&Graphemes::from(""), &Graphemes::from("a")), f64::INFINITY);
+        // on a single word, the character error rate is the word error rate of its graphemes
+        let (word, typo) = (Graphemes::from("receive"), Graphemes::from("recieve"));
+        assert_eq!(character_error_rate(&word, &typo), word_error_rate_with(&word, &typo, &CharTokenizer));
+        assert_eq!(character_error_rate(&word, &typo), 2.0 / 7.0);
+        assert_eq!(word_error_rate(&word, &typo), 1.0);
+    }
+
+    #[test]
+    fn word_error_rate_detailed_test() {
+        let dictionary = crate::dictionary_from(&["we", "canon", "see", "ash", "ort", "distance", "ahead"]);
+        let predicted_sentence = max_match(&Graphemes::from("wecanonlyseeashortdistanceahead"), &dictionary);
+        let actual_sentence = Graphemes::from("we can only see a short distance ahead");
+        let report = word_error_rate_detailed(&actual_sentence, &predicted_sentence);
+        assert_eq!(report.wer, word_error_rate(&actual_sentence, &predicted_sentence));
+        assert_eq!(report.reference_words, 8);
+        // "can only see a short" against "canon l y see ash ort"
+        assert_eq!((report.substitutions, report.insertions, report.deletions), (4, 1, 0));
+
+        let report = word_error_rate_detailed(&actual_sentence, &actual_sentence);
+        assert_eq!((report.substitutions, report.insertions, report.deletions, report.wer), (0, 0, 0, 0.0));
+        let report = word_error_rate_detailed(&Graphemes::from("北京 烤鸭"), &Graphemes::from("北京"));
+        assert_eq!((report.substitutions, report.insertions, report.deletions, report.wer), (0, 0, 1, 0.5));
+    }
+
+    #[test]
+    fn min_wer_over_references_test() {
+        let references = vec![Graphemes::from("we can see a short distance"), Graphemes::from("we can only see a short distance ahead")];
+        let predicted_sentence = Graphemes::from("we can only see a short distance");
+        assert_eq!(word_error_rate(&references[0], &predicted_sentence), 1.0 / 6.0);
+        assert_eq!(min_wer_over_references(&references, &predicted_sentence), (1.0 / 8.0, 1));
+        // ties keep the first reference
+        let same = vec![Graphemes::from("北京 烤鸭"), Graphemes::from("北京 烤鸭")];
+        assert_eq!(min_wer_over_references(&same, &Graphemes::from("北京 烤鸭")), (0.0, 0));
+        assert_eq!(min_wer_over_references(&references[..1], &predicted_sentence), (1.0 / 6.0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "references must not be empty")]
+    fn min_wer_over_references_empty_test() {
+        min_wer_over_references(&[], &Graphemes::from("hello"));
+    }
+
+    #[test]
+    fn dual_error_rates_test() {
+        let mut dictionary : HashSet<Graphemes> = HashSet::new();
+        for word in ["we", "canon", "see", "ash", "ort", "distance", "ahead"].iter() {
+            dictionary.insert(Graphemes::from(word));
+        }
+        let predicted_sentence = max_match(&Graphemes::from("wecanonlyseeashortdistanceahead"), &dictionary);
+        let actual_sentence = Graphemes::from("we can only see a short distance ahead");
+        let (wer, cer) = dual_error_rates(&actual_sentence, &predicted_sentence);
+        assert_eq!(wer, word_error_rate(&actual_sentence, &predicted_sentence));
+        assert_eq!(wer, 0.625);
+        assert_eq!(cer, word_error_rate_with(&actual_sentence, &predicted_sentence, &CharTokenizer));
+        // three spaces inserted and two deleted out of 38 graphemes
+        assert_eq!(cer, 5.0 / 38.0);
+        assert_eq!(dual_error_rates(&actual_sentence, &actual_sentence), (0.0, 0.0));
+        assert_eq!(dual_error_rates(&Graphemes::from("北京 烤鸭"), &Graphemes::from("北京 烤")), (0.5, 0.2));
+    }
+
+    #[test]
+    fn word_move_distance_test() {
+        let distance = |actual, predict| word_move_distance(&Graphemes::from(actual), &Graphemes::from(predict));
+        let word_distance = |actual, predict| levenshtein_distance(&Graphemes::from(actual).split(" "), &Graphemes::from(predict).split(" "), 1);
+        // swapping two words is one move instead of two substitutions
+        assert_eq!(distance("hello big world", "hello world big"), 1);
+        assert_eq!(word_distance("hello big world", "hello world big"), 2);
+        assert_eq!(distance("北京 烤鸭 好吃", "烤鸭 好吃 北京"), 1);
+        // a move followed by a substitution
+        assert_eq!(distance("we can only see a short distance ahead", "short distance ahead we can only see b"), 2);
+        assert_eq!(distance("a b", "b a"), 1);
+        assert_eq!(distance("hello world", "hello world"), 0);
+        assert_eq!(distance("hello world", "goodbye moon"), 2);
+        for &(actual, predict) in [("the cat sat on the mat", "on the mat the cat sat"), ("a b c d", "d c b a"), ("a", "a b c")].iter() {
+            assert!(distance(actual, predict) <= word_distance(actual, predict));
+        }
+    }
+
+    #[test]
+    fn word_error_rate_with_tokenizer_test() {
+        let actual_sentence = Graphemes::from("we can only see a short distance ahead");
+        let predicted_sentence = Graphemes::from("we can only see, a short-distance ahead");
+        let tokenizers : [&dyn Tokenizer; 3] = [&WhitespaceTokenizer, &UnicodeWordTokenizer, &CharTokenizer];
+        let rates : Vec<f64> = tokenizers.iter()
+            .map(|tokenizer| word_error_rate_with(&actual_sentence, &predicted_sentence, *tokenizer))
+            .collect();
+        // "see," and "short-distance" are wrong words when only splitting on whitespace
+        assert_eq!(rates[0], 3.0 / 8.0);
+        // punctuation is dropped by the word tokenizer
+        assert_eq!(rates[1], 0.0);
+        // one inserted comma and one substituted space out of 38 graphemes
+        assert_eq!(rates[2], 2.0 / 38.0);
+        assert_eq!(word_error_rate_with(&actual_sentence, &actual_sentence, &WhitespaceTokenizer), 0.0);
+    }
+
+    #[test]
+    fn levenshtein_stemmed_test() {
+        let running = vec![Graphemes::from("running")];
+        let runs = vec![Graphemes::from("runs")];
+        assert_eq!(levenshtein_distance(&running, &runs, 1), 1);
+        assert_eq!(levenshtein_stemmed(&running, &runs, &SuffixStripStemmer, 1), 0);
+        let sentence1 = Graphemes::from("she jumped over the walls");
+        let sentence2 = Graphemes::from("she jumps over a wall");
+        assert_eq!(levenshtein_stemmed(&sentence1.split(" "), &sentence2.split(" "), &SuffixStripStemmer, 1), 1);
+        assert_eq!(levenshtein_stemmed(&[], &runs, &SuffixStripStemmer, 1), 1);
+    }
+
+    #[test]
+    fn levenshtein_ratio_test() {
+        assert_eq!(levenshtein_ratio(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京烤鸭")), 1.0);
+        assert_eq!(levenshtein_ratio(&Graphemes::from("abcd"), &Graphemes::from("wxyz")), 0.0);
+        assert_eq!(levenshtein_ratio(&Graphemes::from(""), &Graphemes::from("")), 1.0);
+        assert_eq!(levenshtein_ratio(&Graphemes::from(""), &Graphemes::from("北京")), 0.0);
+        assert_eq!(levenshtein_ratio(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京")), 0.5);
+        assert!((levenshtein_ratio(&Graphemes::from("kitten"), &Graphemes::from("sitting")) - 4.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn jaro_similarity_test() {
+        let jaro = |word1, word2| jaro_similarity(&Graphemes::from(word1), &Graphemes::from(word2));
+        assert!((jaro("martha", "marhta") - 0.944).abs() < 0.001);
+        assert!((jaro("dwayne", "duane") - 0.822).abs() < 0.001);
+        assert!((jaro("dixon", "dicksonx") - 0.767).abs() < 0.001);
+        assert_eq!(jaro("北京烤鸭", "北京烤鸭"), 1.0);
+        assert_eq!(jaro("abc", "xyz"), 0.0);
+        assert_eq!(jaro("", ""), 1.0);
+        assert_eq!(jaro("", "北京"), 0.0);
+        // graphemes are compared, not bytes or chars
+        assert!((jaro("cafe\u{301}", "café") - 5.0 / 6.0).abs() < 1e-9);
+        assert_eq!(jaro("martha", "marhta"), jaro("marhta", "martha"));
+    }
+
+    #[test]
+    fn jaro_winkler_similarity_test() {
+        let jaro_winkler = |word1, word2, prefix_scale| jaro_winkler_similarity(&Graphemes::from(word1), &Graphemes::from(word2), prefix_scale);
+        assert!((jaro_winkler("martha", "marhta", 0.1) - 0.961).abs() < 0.001);
+        assert!((jaro_winkler("dwayne", "duane", 0.1) - 0.84).abs() < 0.001);
+        assert!((jaro_winkler("dixon", "dicksonx", 0.1) - 0.813).abs() < 0.001);
+        assert_eq!(jaro_winkler("martha", "marhta", 0.0), jaro_similarity(&Graphemes::from("martha"), &Graphemes::from("marhta")));
+        // the prefix counts at most four graphemes and the scale at most 0.25
+        assert!((jaro_winkler("北京烤鸭店", "北京烤鸭馆", 0.25) - 1.0).abs() < 1e-9);
+        assert!(jaro_winkler("abcdefgh", "abcdefgx", 10.0) <= 1.0);
+        assert_eq!(jaro_winkler("abcdefgh", "abcdefgx", 10.0), jaro_winkler("abcdefgh", "abcdefgx", 0.25));
+    }
+
+    #[test]
+    fn jaccard_qgram_test() {
+        // {ni, ig, gh, ht} ∩ {na, ac, ch, ht} = {ht}, union has 7 bigrams
+        assert_eq!(jaccard_qgram(&Graphemes::from("night"), &Graphemes::from("nacht"), 2), 1.0 / 7.0);
+        // repeated q-grams count once: {ab, ba} and {ab}
+        assert_eq!(jaccard_qgram(&Graphemes::from("abab"), &Graphemes::from("ab"), 2), 0.5);
+        assert_eq!(jaccard_qgram(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京烤鸭"), 3), 1.0);
+        assert_eq!(jaccard_qgram(&Graphemes::from("abc"), &Graphemes::from("xyz"), 1), 0.0);
+        assert_eq!(jaccard_qgram(&Graphemes::from("a"), &Graphemes::from("a"), 2), 1.0);
+        assert_eq!(jaccard_qgram(&Graphemes::from("a"), &Graphemes::from("b"), 2), 0.0);
+        assert_eq!(jaccard_qgram(&Graphemes::from(""), &Graphemes::from(""), 2), 1.0);
+    }
+
+    #[test]
+    fn ngram_cosine_test() {
+        assert_eq!(ngram_cosine(&Graphemes::from("night"), &Graphemes::from("nacht"), 2), 0.25);
+        // {ab: 2, ba: 1} · {ab: 1} = 2, norms √5 and 1
+        assert!((ngram_cosine(&Graphemes::from("abab"), &Graphemes::from("ab"), 2) - 2.0 / 5f64.sqrt()).abs() < 1e-12);
+        // {北京: 1, 京烤: 1, 烤鸭: 1} · {北京: 1, 京烤: 1} = 2, norms √3 and √2
+        assert!((ngram_cosine(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京烤"), 2) - 2.0 / 6f64.sqrt()).abs() < 1e-12);
+        assert!((ngram_cosine(&Graphemes::from("北京烤鸭"), &Graphemes::from("北京烤鸭"), 3) - 1.0).abs() < 1e-12);
+        assert_eq!(ngram_cosine(&Graphemes::from("abc"), &Graphemes::from("xyz"), 1), 0.0);
+        assert_eq!(ngram_cosine(&Graphemes::from("a"), &Graphemes::from("a"), 2), 1.0);
+        assert_eq!(ngram_cosine(&Graphemes::from("a"), &Graphemes::from("abc"), 2), 0.0);
+        assert_eq!(ngram_cosine(&Graphemes::from(""), &Graphemes::from(""), 2), 1.0);
+    }
+
+    #[test]
+    fn analyze_test() {
+        assert_eq!(analyze(&Graphemes::from("book"), &Graphemes::from("back"), 1),
+                   DistanceResult { distance: 2, similarity: 0.5, max_len: 4 });
+        assert_eq!(analyze(&Graphemes::from(""), &Graphemes::from(""), 1),
+                   DistanceResult { distance: 0, similarity: 1.0, max_len: 0 });
+        assert_eq!(analyze(&Graphemes::from("ab"), &Graphemes::from("cd"), 2),
+                   DistanceResult { distance: 4, similarity: 0.0, max_len: 2 });
+        let words = analyze(&Graphemes::from("we can see").split(" "), &Graphemes::from("we see").split(" "), 1);
+        assert_eq!((words.distance, words.max_len), (1, 3));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn wer_bootstrap_ci_test() {
+        use crate::metrics::wer_bootstrap_ci;
+        let pairs = vec![
+            (Graphemes::from("we can only see"), Graphemes::from("we canon l y see")),
+            (Graphemes::from("a short distance ahead"), Graphemes::from("ash ort distance ahead")),
+            (Graphemes::from("他 特别 喜欢 北京烤鸭"), Graphemes::from("他 特别 喜欢 北京烤鸭")),
+            (Graphemes::from("hello world"), Graphemes::from("hello word")),
+        ];
+        let (wer, lower, upper) = wer_bootstrap_ci(&pairs, 500, 0.9, 7);
+        assert_eq!(wer, 6.0 / 14.0);
+        assert!(lower <= wer && wer <= upper);
+        assert!(lower < upper);
+        assert_eq!(wer_bootstrap_ci(&pairs, 500, 0.9, 7), (wer, lower, upper));
+        // a wider confidence level never narrows the interval
+        let (_, wide_lower, wide_upper) = wer_bootstrap_ci(&pairs, 500, 0.99, 7);
+        assert!(wide_lower <= lower && upper <= wide_upper);
+        assert_eq!(wer_bootstrap_ci(&pairs[2..3], 100, 0.95, 7), (0.0, 0.0, 0.0));
+        assert_eq!(wer_bootstrap_ci(&[], 100, 0.95, 7), (0.0, 0.0, 0.0));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    #[should_panic(expected = "confidence must be between 0 and 1")]
+    fn wer_bootstrap_ci_confidence_panic_test() {
+        use crate::metrics::wer_bootstrap_ci;
+        wer_bootstrap_ci(&[(Graphemes::from("we see"), Graphemes::from("we see"))], 100, 1.0, 7);
+    }
+
+    #[test]
+    fn typo_likelihood_test() {
+        let classify = |word1, word2| typo_likelihood(&Graphemes::from(word1), &Graphemes::from(word2));
+        assert_eq!(classify("recieve", "receive"), TypoClass::LikelyTypo);
+        assert_eq!(classify("cat", "elephant"), TypoClass::Unrelated);
+        assert_eq!(classify("receive", "receive"), TypoClass::Exact);
+        assert_eq!(classify("", ""), TypoClass::Exact);
+        // a swap is a single edit, while levenshtein needs two
+        assert_eq!(levenshtein_distance(&Graphemes::from("form"), &Graphemes::from("from"), 1), 2);
+        assert_eq!(classify("form", "from"), TypoClass::LikelyTypo);
+        // short words allow one edit, longer words one edit per four graphemes
+        assert_eq!(classify("cat", "cut"), TypoClass::LikelyTypo);
+        assert_eq!(classify("cat", "cup"), TypoClass::Unrelated);
+        assert_eq!(classify("distance", "distnace"), TypoClass::LikelyTypo);
+        assert_eq!(classify("distance", "dustnace"), TypoClass::LikelyTypo);
+        assert_eq!(classify("distance", "dustnacf"), TypoClass::Unrelated);
+        assert_eq!(classify("北京烤鸭", "北烤京鸭"), TypoClass::LikelyTypo);
+        assert_eq!(classify("a", ""), TypoClass::LikelyTypo);
+    }
+
+    #[test]
+    fn common_prefix_suffix_len_test() {
+        assert_eq!(common_prefix_len(&Graphemes::from("prefix_foo"), &Graphemes::from("prefix_bar")), 7);
+        assert_eq!(common_suffix_len(&Graphemes::from("prefix_foo"), &Graphemes::from("prefix_bar")), 0);
+        assert_eq!(common_suffix_len(&Graphemes::from("foo_suffix"), &Graphemes::from("bar_suffix")), 7);
+        assert_eq!(common_prefix_len(&Graphemes::from("foo_suffix"), &Graphemes::from("bar_suffix")), 0);
+        // one word contains the other
+        assert_eq!(common_prefix_len(&Graphemes::from("book"), &Graphemes::from("bookkeeper")), 4);
+        assert_eq!(common_suffix_len(&Graphemes::from("keeper"), &Graphemes::from("bookkeeper")), 6);
+        assert_eq!(common_prefix_len(&Graphemes::from(""), &Graphemes::from("a")), 0);
+        // graphemes differing only in a combining mark do not match
+        assert_eq!(common_prefix_len(&Graphemes::from("cafe\u{301}s"), &Graphemes::from("cafes")), 3);
+        assert_eq!(common_suffix_len(&vec!["we", "can", "see"], &vec!["you", "can", "see"]), 2);
+    }
+
+    #[test]
+    fn circular_levenshtein_test() {
+        assert_eq!(levenshtein_distance(&Graphemes::from("abcd"), &Graphemes::from("cdab"), 1), 4);
+        assert_eq!(circular_levenshtein(&Graphemes::from("abcd"), &Graphemes::from("cdab"), 1), 0);
+        assert_eq!(circular_levenshtein(&Graphemes::from("abcd"), &Graphemes::from("dabc"), 1), 0);
+        assert_eq!(circular_levenshtein(&Graphemes::from("abcd"), &Graphemes::from("cdxb"), 1), 1);
+        assert_eq!(circular_levenshtein(&Graphemes::from("北京烤鸭"), &Graphemes::from("烤鸭北"), 1), 1);
+        assert_eq!(circular_levenshtein(&Graphemes::from("abc"), &Graphemes::from("xyz"), 1), 3);
+        assert_eq!(circular_levenshtein(&Graphemes::from(""), &Graphemes::from("ab"), 1), 2);
+        assert_eq!(circular_levenshtein(&Graphemes::from("ab"), &Graphemes::from(""), 1), 2);
+    }
+
+    #[test]
+    fn levenshtein_pattern_test() {
+        let year = vec![GraphemeClass::Digit; 4];
+        assert_eq!(levenshtein_pattern(&Graphemes::from("2024"), &year, 1), 0);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("224"), &year, 1), 1);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("20245"), &year, 1), 1);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("yyyy"), &year, 1), 4);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("yyyy"), &year, 2), 8);
+        assert_eq!(levenshtein_pattern(&Graphemes::from(""), &year, 1), 4);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("ab"), &[], 1), 2);
+        // a license plate pattern like `[AB]\d .`
+        let plate = vec![
+            GraphemeClass::OneOf(vec![String::from("A"), String::from("B")]),
+            GraphemeClass::Digit,
+            GraphemeClass::Whitespace,
+            GraphemeClass::Any,
+        ];
+        assert_eq!(levenshtein_pattern(&Graphemes::from("B7 北"), &plate, 1), 0);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("C7 é"), &plate, 1), 1);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("京"), &[GraphemeClass::Literal(String::from("京"))], 1), 0);
+        assert_eq!(levenshtein_pattern(&Graphemes::from("京"), &[GraphemeClass::Alphabetic], 1), 0);
+    }
+
+    #[test]
+    fn flex_distance_test() {
+        let pattern = FlexPattern::from("colo(u)r");
+        let distance = |query| flex_distance(&Graphemes::from(query), &pattern, 1);
+        assert_eq!(distance("color"), 0);
+        assert_eq!(distance("colour"), 0);
+        assert_eq!(distance("colur"), 1);
+        assert_eq!(distance("colouur"), 1);
+        assert_eq!(distance(""), 5);
+        // a group of optional graphemes is skipped grapheme by grapheme
+        let pattern = FlexPattern::from("北京(烤鸭)");
+        assert_eq!(flex_distance(&Graphemes::from("北京"), &pattern, 1), 0);
+        assert_eq!(flex_distance(&Graphemes::from("北京烤"), &pattern, 1), 0);
+        assert_eq!(flex_distance(&Graphemes::from("北京烤鸭"), &pattern, 1), 0);
+        assert_eq!(flex_distance(&Graphemes::from("北京鸭子"), &pattern, 1), 1);
+        // without optional graphemes it is the levenshtein distance
+        assert_eq!(flex_distance(&Graphemes::from("kitten"), &FlexPattern::from("sitting"), 1), 3);
+        let mut literal = FlexPattern::new();
+        literal.push("(", false);
+        literal.push("a", true);
+        assert_eq!(flex_distance(&Graphemes::from("("), &literal, 1), 0);
+        assert!(FlexPattern::from("").is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn flex_pattern_unbalanced_test() {
+        FlexPattern::from("colo(u(r)");
+    }
+
+    #[test]
+    fn boundary_prf_test() {
+        let gold = Graphemes::from("他 特别 喜欢 北京烤鸭");
+        assert_eq!(boundary_prf(&gold, &gold), (1.0, 1.0, 1.0));
+        // an extra boundary inside "北京烤鸭" lowers the precision only
+        let (precision, recall, f1) = boundary_prf(&gold, &Graphemes::from("他 特别 喜欢 北京 烤鸭"));
+        assert_eq!((precision, recall), (0.75, 1.0));
+        assert!((f1 - 6.0 / 7.0).abs() < 1e-12);
+        // a missing boundary lowers the recall only
+        assert_eq!(boundary_prf(&gold, &Graphemes::from("他 特别 喜欢北京烤鸭")), (1.0, 2.0 / 3.0, 0.8));
+        // leading, trailing and repeated spaces are not boundaries of their own
+        assert_eq!(boundary_prf(&gold, &Graphemes::from(" 他  特别 喜欢 北京烤鸭 ")), (1.0, 1.0, 1.0));
+        assert_eq!(boundary_prf(&Graphemes::from("北京"), &Graphemes::from("北 京")), (0.0, 1.0, 0.0));
+        assert_eq!(boundary_prf(&Graphemes::from(""), &Graphemes::from("")), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn boundary_prf_different_sentences_test() {
+        boundary_prf(&Graphemes::from("他 特别"), &Graphemes::from("她 特别"));
+    }
+
+    #[test]
+    fn segmentation_agreement_test() {
+        let segmentation1 = Graphemes::from("他 特别 喜欢 北京烤鸭");
+        let segmentation2 = Graphemes::from("他特别 喜欢 北京 烤鸭");
+        // of the 8 positions, they disagree after "他" and between "北京" and "烤鸭"
+        assert_eq!(segmentation_agreement(&segmentation1, &segmentation2), 6.0 / 8.0);
+        assert_eq!(segmentation_agreement(&segmentation2, &segmentation1), 6.0 / 8.0);
+        assert_eq!(segmentation_agreement(&segmentation1, &segmentation1), 1.0);
+        assert_eq!(segmentation_agreement(&Graphemes::from("a b c"), &Graphemes::from("abc")), 0.0);
+        // leading, trailing and repeated spaces do not matter
+        assert_eq!(segmentation_agreement(&segmentation1, &Graphemes::from(" 他  特别 喜欢 北京烤鸭 ")), 1.0);
+        assert_eq!(segmentation_agreement(&Graphemes::from("北"), &Graphemes::from(" 北")), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn segmentation_agreement_different_sentences_test() {
+        segmentation_agreement(&Graphemes::from("他 特别"), &Graphemes::from("她 特别"));
+    }
+
+    #[test]
+    fn confusion_matrix_test() {
+        let mut confusions = ConfusionMatrix::new();
+        assert!(confusions.most_frequent(3).is_empty());
+        confusions.add(&Graphemes::from("we can see their house"), &Graphemes::from("we can sea there house"));
+        confusions.add(&Graphemes::from("their car is there"), &Graphemes::from("there car is there"));
+        // the deleted "sea" is not a confusion
+        confusions.add(&Graphemes::from("see the sea"), &Graphemes::from("sea the"));
+        assert_eq!(confusions.count("their", "there"), 2);
+        assert_eq!(confusions.count("see", "sea"), 2);
+        assert_eq!(confusions.count("there", "their"), 0);
+        let word = |word : &str| String::from(word);
+        assert_eq!(confusions.most_frequent(3), vec![(word("see"), word("sea"), 2), (word("their"), word("there"), 2)]);
+        assert_eq!(confusions.most_frequent(1), vec![(word("see"), word("sea"), 2)]);
+    }
+
+    #[test]
+    fn distance_cache_test() {
+        let words : Vec<Graphemes> = ["kitten", "sitting", "北京", "南京", "book", "back"].iter().map(|word| Graphemes::from(word)).collect();
+        let mut cache = DistanceCache::new(2);
+        assert!(cache.is_empty());
+        for _ in 0..2 {
+            assert_eq!(cache.cached_distance(&words[0], &words[1], 1), 3);
+            assert_eq!(cache.cached_distance(&words[0], &words[1], 2), 5);
+        }
+        assert_eq!(cache.len(), 2);
+        // "kitten"/"sitting" with a substitution cost of 1 is the least recently used entry
+        assert_eq!(cache.cached_distance(&words[2], &words[3], 1), 1);
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&words[0], &words[1], 1));
+        assert!(cache.contains(&words[0], &words[1], 2));
+        // using an entry makes it the most recently used
+        assert_eq!(cache.cached_distance(&words[0], &words[1], 2), 5);
+        assert_eq!(cache.cached_distance(&words[4], &words[5], 1), 2);
+        assert!(cache.contains(&words[0], &words[1], 2));
+        assert!(!cache.contains(&words[2], &words[3], 1));
+        // cached results match fresh computations
+        for word1 in words.iter() {
+            for word2 in words.iter() {
+                assert_eq!(cache.cached_distance(word1, word2, 1), levenshtein_distance(word1, word2, 1));
+                assert_eq!(cache.cached_distance(word1, word2, 1), levenshtein_distance(word1, word2, 1));
+            }
+        }
+        let mut no_cache = DistanceCache::new(0);
+        assert_eq!(no_cache.cached_distance(&words[4], &words[5], 1), 2);
+        assert!(no_cache.is_empty());
+    }
+
+    #[test]
+    fn distance_cache_grapheme_boundaries_test() {
+        // the same text split into different graphemes must not share a cache entry
+        let single : Graphemes = vec!["ab"].into_iter().collect();
+        let pair = Graphemes::from("ab");
+        let mut cache = DistanceCache::new(10);
+        assert_eq!(cache.cached_distance(&pair, &pair, 1), 0);
+        assert!(!cache.contains(&single, &pair, 1));
+        assert_eq!(cache.cached_distance(&single, &pair, 1), 2);
+        assert_eq!(cache.cached_distance(&single, &pair, 1), levenshtein_distance(&single, &pair, 1));
+        assert_eq!(cache.cached_distance(&single, &single, 1), 0);
+        assert_eq!(cache.len(), 3);
+    }
+}
@@ -0,0 +1,191 @@
+pub use self::segmenter::{
+    FrozenDictionary,
+    Segmenter
+};
+
+/// Dictionary segmentation backed by an immutable, interned dictionary
+mod segmenter {
+    use std::collections::{HashMap, HashSet};
+    use len_trait::len::Len;
+    use push_trait::base::Push;
+    use crate::graphemes_struct::Graphemes;
+
+    /// Id given to graphemes of a sentence which appear in no dictionary word
+    const UNKNOWN : u32 = u32::MAX;
+
+    /// Immutable dictionary for serving segmentation. Every distinct grapheme is stored once and words are
+    /// kept as sequences of grapheme ids, so the dictionary owns its strings without borrowing the source.
+    /// The length of the longest word is computed once to bound the candidates tried at each position.
+    #[derive(Debug, Clone)]
+    pub struct FrozenDictionary {
+        grapheme_ids : HashMap<Box<str>, u32>,
+        words : HashSet<Box<[u32]>>,
+        max_word_len : usize,
+    }
+
+    impl FrozenDictionary {
+        /// Builds a frozen dictionary from the words of a `HashSet` dictionary. Empty words are dropped
+        /// since segmentation never matches them.
+        ///
+        /// # Arguments
+        /// * `dictionary` - Words to freeze
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::dictionary_from;
+        /// use nlp::segmenter::FrozenDictionary;
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let frozen = FrozenDictionary::from(&dictionary_from(&["北京", "北京烤鸭", "烤鸭"]));
+        /// assert_eq!(frozen.len(), 3);
+        /// assert_eq!(frozen.unique_graphemes(), 4);
+        /// assert_eq!(frozen.max_word_len(), 4);
+        /// assert!(frozen.contains(&Graphemes::from("烤鸭")));
+        /// ```
+        pub fn from(dictionary : &HashSet<Graphemes>) -> FrozenDictionary {
+            let mut grapheme_ids : HashMap<Box<str>, u32> = HashMap::new();
+            let mut words = HashSet::with_capacity(dictionary.len());
+            let mut max_word_len = 0;
+            for word in dictionary.iter().filter(|word| !word.is_empty()) {
+                let ids : Box<[u32]> = word.iter().map(|grapheme| {
+                    let next_id = grapheme_ids.len() as u32;
+                    *grapheme_ids.entry(Box::from(*grapheme)).or_insert(next_id)
+                }).collect();
+                max_word_len = max_word_len.max(ids.len());
+                words.insert(ids);
+            }
+            grapheme_ids.shrink_to_fit();
+            FrozenDictionary { grapheme_ids, words, max_word_len }
+        }
+
+        /// Returns whether `word` is in the dictionary
+        pub fn contains(&self, word : &Graphemes) -> bool {
+            word.len() <= self.max_word_len && self.words.contains(&self.ids(word)[..])
+        }
+
+        /// Returns the number of words
+        pub fn len(&self) -> usize {
+            self.words.len()
+        }
+
+        /// Returns whether the dictionary has no words
+        pub fn is_empty(&self) -> bool {
+            self.words.is_empty()
+        }
+
+        /// Returns the number of distinct grapheme strings stored for all the words
+        pub fn unique_graphemes(&self) -> usize {
+            self.grapheme_ids.len()
+        }
+
+        /// Returns the number of graphemes in the longest word
+        pub fn max_word_len(&self) -> usize {
+            self.max_word_len
+        }
+
+        fn ids(&self, graphemes : &Graphemes) -> Vec<u32> {
+            graphemes.iter().map(|grapheme| *self.grapheme_ids.get(*grapheme).unwrap_or(&UNKNOWN)).collect()
+        }
+    }
+
+    /// Maximum matching segmenter over a `FrozenDictionary`
+    #[derive(Debug, Clone)]
+    pub struct Segmenter {
+        dictionary : FrozenDictionary,
+    }
+
+    impl Segmenter {
+        /// Creates a segmenter matching words of a frozen dictionary
+        ///
+        /// # Arguments
+        /// * `dictionary` - Words to match
+        pub fn from_frozen(dictionary : FrozenDictionary) -> Segmenter {
+            Segmenter { dictionary }
+        }
+
+        /// Returns the dictionary the segmenter matches against
+        pub fn dictionary(&self) -> &FrozenDictionary {
+            &self.dictionary
+        }
+
+        /// Segments a sentence with space like `max_match`: at each position the longest dictionary word
+        /// is taken, or a single grapheme when no word matches. Only words up to the longest dictionary word
+        /// are tried and graphemes absent from the dictionary stop the search early.
+        ///
+        /// # Arguments
+        /// * `sentence` - Sentence to be segmented
+        ///
+        /// # Example
+        /// ```
+        /// use nlp::{dictionary_from, max_match};
+        /// use nlp::segmenter::{FrozenDictionary, Segmenter};
+        /// use nlp::graphemes_struct::Graphemes;
+        /// let dictionary = dictionary_from(&["他", "特别", "喜欢", "北京烤鸭"]);
+        /// let segmenter = Segmenter::from_frozen(FrozenDictionary::from(&dictionary));
+        /// let sentence = Graphemes::from("他特别喜欢北京烤鸭");
+        /// assert_eq!(segmenter.segment(&sentence), Graphemes::from("他 特别 喜欢 北京烤鸭"));
+        /// assert_eq!(segmenter.segment(&sentence), max_match(&sentence, &dictionary));
+        /// ```
+        pub fn segment<'a>(&self, sentence : &Graphemes<'a>) -> Graphemes<'a> {
+            let ids = self.dictionary.ids(sentence);
+            let mut segmented = Graphemes::new();
+            let mut start = 0;
+            while start < sentence.len() {
+                let known_end = ids[start..].iter().position(|&id| id == UNKNOWN).map_or(ids.len(), |offset| start + offset);
+                let longest_end = known_end.min(start + self.dictionary.max_word_len);
+                let end = (start+2..longest_end+1).rev()
+                    .find(|&end| self.dictionary.words.contains(&ids[start..end]))
+                    .unwrap_or(start + 1);
+                if start > 0 {
+                    segmented.push(" ");
+                }
+                segmented.append(sentence.slice(start, end));
+                start = end;
+            }
+            segmented
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cases {
+    use super::segmenter::*;
+    use crate::{dictionary_from, max_match};
+    use crate::graphemes_struct::Graphemes;
+    use len_trait::len::Len;
+
+    #[test]
+    fn frozen_dictionary_test() {
+        let dictionary = dictionary_from(&["他", "特别", "", "喜欢", "北京", "北京烤鸭", "烤鸭", "特别喜欢"]);
+        let frozen = FrozenDictionary::from(&dictionary);
+        assert_eq!(frozen.len(), 7);
+        assert_eq!(frozen.max_word_len(), 4);
+        for word in dictionary.iter().filter(|word| !word.is_empty()) {
+            assert!(frozen.contains(word));
+        }
+        assert!(!frozen.contains(&Graphemes::from("")));
+        assert!(!frozen.contains(&Graphemes::from("京烤")));
+        assert!(!frozen.contains(&Graphemes::from("他们")));
+        assert!(!frozen.contains(&Graphemes::from("他特别喜欢北京烤鸭")));
+        // shared graphemes are stored once instead of once per word
+        let total_graphemes : usize = dictionary.iter().map(|word| word.len()).sum();
+        assert_eq!(total_graphemes, 17);
+        assert_eq!(frozen.unique_graphemes(), 9);
+
+        let empty = FrozenDictionary::from(&dictionary_from(&[""]));
+        assert!(empty.is_empty());
+        assert_eq!(empty.max_word_len(), 0);
+    }
+
+    #[test]
+    fn segment_test() {
+        let dictionary = dictionary_from(&["他", "特别", "喜欢", "北京", "北京烤鸭", "烤鸭", "特别喜欢", "we", "canon", "see"]);
+        let segmenter = Segmenter::from_frozen(FrozenDictionary::from(&dictionary));
+        let sentences = ["他特别喜欢北京烤鸭", "他特别喜欢北京", "北京烤", "他们特别喜欢rust", "wecanonsee", "", "x"];
+        for sentence in sentences.iter() {
+            let sentence = Graphemes::from(sentence);
+            assert_eq!(segmenter.segment(&sentence), max_match(&sentence, &dictionary));
+        }
+        assert_eq!(segmenter.segment(&Graphemes::from("他特别喜欢北京烤鸭")), Graphemes::from("他 特别喜欢 北京烤鸭"));
+        assert_eq!(segmenter.dictionary().len(), dictionary.len());
+    }
+}
@@ -0,0 +1,72 @@
+pub use self::stemmer::{
+    Stemmer,
+    SuffixStripStemmer
+};
+
+/// Reduction of words to their stems
+mod stemmer {
+    use len_trait::len::Len;
+    use crate::graphemes_struct::Graphemes;
+
+    /// Reduces a word to its stem.
+    /// Stems borrow from the same source string as the word.
+    pub trait Stemmer {
+        fn stem<'a>(&self, word : &Graphemes<'a>) -> Graphemes<'a>;
+    }
+
+    /// Strips the longest common English inflectional suffix from a word, keeping at least three graphemes.
+    /// A doubled final consonant left behind by "-ing" or "-ed" is undoubled ("running" → "run").
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct SuffixStripStemmer;
+
+    const SUFFIXES : [&str; 7] = ["ingly", "edly", "ing", "ed", "ly", "es", "s"];
+    const MIN_STEM_LEN : usize = 3;
+
+    impl Stemmer for SuffixStripStemmer {
+        fn stem<'a>(&self, word : &Graphemes<'a>) -> Graphemes<'a> {
+            for suffix in SUFFIXES.iter() {
+                let suffix = Graphemes::from(suffix);
+                if word.len() < suffix.len() + MIN_STEM_LEN || word[word.len() - suffix.len()..] != suffix[..] {
+                    continue;
+                }
+                if suffix[0] == "s" && word[word.len() - 2] == "s" {
+                    // "glass" is not a plural
+                    break;
+                }
+                let mut stem_len = word.len() - suffix.len();
+                let undoubles = suffix[0] == "i" || (suffix[0] == "e" && suffix[1] == "d");
+                let last = word[stem_len - 1];
+                if undoubles && last == word[stem_len - 2] && !["a", "e", "i", "o", "u", "l", "s", "z"].contains(&last) {
+                    stem_len -= 1;
+                }
+                return word.slice(0, stem_len);
+            }
+            word.slice(0, word.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cases {
+    use super::stemmer::*;
+    use crate::graphemes_struct::Graphemes;
+
+    fn stem(word : &str) -> String {
+        SuffixStripStemmer.stem(&Graphemes::from(word)).to_string()
+    }
+
+    #[test]
+    fn suffix_strip_stemmer_test() {
+        assert_eq!(stem("running"), "run");
+        assert_eq!(stem("runs"), "run");
+        assert_eq!(stem("stopped"), "stop");
+        assert_eq!(stem("jumped"), "jump");
+        assert_eq!(stem("falling"), "fall");
+        assert_eq!(stem("quickly"), "quick");
+        assert_eq!(stem("glass"), "glass");
+        assert_eq!(stem("sing"), "sing");
+        assert_eq!(stem("is"), "is");
+        assert_eq!(stem(""), "");
+        assert_eq!(stem("北京"), "北京");
+    }
+}
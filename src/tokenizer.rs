@@ -0,0 +1,102 @@
+pub use self::tokenizer::{
+    Tokenizer,
+    WhitespaceTokenizer,
+    CharTokenizer,
+    UnicodeWordTokenizer
+};
+
+/// Strategies for splitting graphemes into tokens
+mod tokenizer {
+    extern crate unicode_segmentation;
+    use unicode_segmentation::UnicodeSegmentation;
+    use len_trait::len::Len;
+    use crate::graphemes_struct::Graphemes;
+
+    /// Splits a sequence of graphemes into tokens.
+    /// Tokens borrow from the same source string as the input graphemes.
+    pub trait Tokenizer {
+        fn tokenize<'a>(&self, graphemes : &Graphemes<'a>) -> Vec<Graphemes<'a>>;
+    }
+
+    /// Splits on runs of whitespace graphemes, dropping empty tokens
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct WhitespaceTokenizer;
+
+    /// Makes every grapheme its own token
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct CharTokenizer;
+
+    /// Splits on Unicode word boundaries (UAX #29), keeping only tokens containing
+    /// an alphanumeric character, so punctuation and whitespace are dropped
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct UnicodeWordTokenizer;
+
+    impl Tokenizer for WhitespaceTokenizer {
+        fn tokenize<'a>(&self, graphemes : &Graphemes<'a>) -> Vec<Graphemes<'a>> {
+            let mut tokens = vec![];
+            let mut token_start = 0;
+            for (i, grapheme) in graphemes.iter().enumerate() {
+                if grapheme.chars().all(char::is_whitespace) {
+                    if token_start < i {
+                        tokens.push(graphemes.slice(token_start, i));
+                    }
+                    token_start = i + 1;
+                }
+            }
+            if token_start < graphemes.len() {
+                tokens.push(graphemes.slice(token_start, graphemes.len()));
+            }
+            tokens
+        }
+    }
+
+    impl Tokenizer for CharTokenizer {
+        fn tokenize<'a>(&self, graphemes : &Graphemes<'a>) -> Vec<Graphemes<'a>> {
+            (0..graphemes.len()).map(|i| graphemes.slice(i, i + 1)).collect()
+        }
+    }
+
+    impl Tokenizer for UnicodeWordTokenizer {
+        fn tokenize<'a>(&self, graphemes : &Graphemes<'a>) -> Vec<Graphemes<'a>> {
+            let string = graphemes.to_string();
+            // byte offset at which each grapheme starts
+            let grapheme_starts : Vec<usize> = graphemes.iter()
+                .scan(0, |offset, grapheme| {
+                    let start = *offset;
+                    *offset += grapheme.len();
+                    Some(start)
+                }).collect();
+            let grapheme_index = |byte_offset : usize| grapheme_starts.binary_search(&byte_offset)
+                .unwrap_or_else(|insert_at| insert_at);
+            string.split_word_bound_indices()
+                .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+                .map(|(start, word)| graphemes.slice(grapheme_index(start), grapheme_index(start + word.len())))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cases {
+    use super::tokenizer::*;
+    use crate::graphemes_struct::Graphemes;
+
+    #[test]
+    fn whitespace_tokenizer_test() {
+        assert_eq!(WhitespaceTokenizer.tokenize(&Graphemes::from(" we  can\tsee\u{3000}far ")),
+                   vec![Graphemes::from("we"), Graphemes::from("can"), Graphemes::from("see"), Graphemes::from("far")]);
+        assert!(WhitespaceTokenizer.tokenize(&Graphemes::from("")).is_empty());
+    }
+
+    #[test]
+    fn char_tokenizer_test() {
+        assert_eq!(CharTokenizer.tokenize(&Graphemes::from("a̐é ö")),
+                   vec![Graphemes::from("a̐"), Graphemes::from("é"), Graphemes::from(" "), Graphemes::from("ö")]);
+    }
+
+    #[test]
+    fn unicode_word_tokenizer_test() {
+        assert_eq!(UnicodeWordTokenizer.tokenize(&Graphemes::from("Hello, wörld! It's 2019.")),
+                   vec![Graphemes::from("Hello"), Graphemes::from("wörld"), Graphemes::from("It's"), Graphemes::from("2019")]);
+    }
+}